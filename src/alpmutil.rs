@@ -1,6 +1,84 @@
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
 use alpm::{Alpm, PackageReason, PackageValidation, SigLevel};
 
+use crate::installed::InstalledPackage;
+use crate::pacmanconf::PacmanConfig;
+use crate::pkgcache::{self, CachedPackage};
+use crate::snapshot::{PackageDiff, PackageSet};
+
+/// Where package information was found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PackageSource {
+    /// The local database of installed packages
+    Local,
+    /// A sync database, by repository name
+    Sync(String),
+    /// A package file in the package cache, for packages that are neither installed nor
+    /// available from any repository
+    Cache(PathBuf),
+}
+
+impl std::fmt::Display for PackageSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PackageSource::Local => write!(f, "installed"),
+            PackageSource::Sync(repo) => write!(f, "{} repository", repo),
+            PackageSource::Cache(path) => write!(f, "package cache ({})", path.display()),
+        }
+    }
+}
+
+/// Which databases pacman has written since they were last read
+#[derive(Debug, Clone, Copy)]
+pub enum DbChange {
+    /// Packages were installed, upgraded or removed, and maybe the sync databases
+    /// refreshed as well
+    Local,
+    /// Only the sync databases were refreshed
+    Sync,
+}
+
+/// Modification times of the databases, to notice pacman writing them. libalpm reads
+/// each database once, so a handle has to be opened again to see the changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DbStamps {
+    local: Option<SystemTime>,
+    sync: Option<SystemTime>,
+}
+
+impl DbStamps {
+    pub fn read(dbpath: &Path) -> Self {
+        let modified = |path: &Path| fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        // Every transaction adds or removes a directory for each package it changes
+        let local = modified(&dbpath.join("local"));
+        let sync = fs::read_dir(dbpath.join("sync")).ok().and_then(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .filter_map(|entry| modified(&entry.path()))
+                .max()
+        });
+        DbStamps { local, sync }
+    }
+
+    /// What changed between these stamps and later ones
+    pub fn change(&self, later: &DbStamps) -> Option<DbChange> {
+        if self.local != later.local {
+            Some(DbChange::Local)
+        } else if self.sync != later.sync {
+            Some(DbChange::Sync)
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
 pub struct PackageInfo {
+    pub source: PackageSource,
     pub version: String,
     pub description: String,
     pub arch: Option<String>,
@@ -19,38 +97,86 @@ pub struct PackageInfo {
     pub validated_by: PackageValidation,
 }
 
+/// How a dependency is satisfied
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Installed,
+    /// Not installed but available from a sync database
+    Available,
+    Missing,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dependency {
+    /// As declared, with any version requirement or description
+    pub spec: String,
+    /// The package that satisfies it, which may provide it under another name
+    pub satisfier: Option<String>,
+    pub resolution: Resolution,
+}
+
+#[derive(Debug, Clone)]
 pub struct Dependencies {
-    pub depends_on: Vec<String>,
-    pub optional_deps: Vec<String>,
-    pub required_by: Vec<String>,
-    pub optional_for: Vec<String>,
+    pub depends_on: Vec<Dependency>,
+    pub optional_deps: Vec<Dependency>,
+    pub required_by: Vec<Dependency>,
+    pub optional_for: Vec<Dependency>,
 }
 
 #[derive(Debug)]
 pub struct AlpmService {
     handle: Alpm,
+    dbpath: PathBuf,
+    cache_dirs: Vec<PathBuf>,
 }
 
 impl AlpmService {
-    pub fn new() -> Result<Self, alpm::Error> {
-        let handle = Alpm::new("/", "/var/lib/pacman")?;
-        handle
-            .register_syncdb("core", SigLevel::USE_DEFAULT)
-            .unwrap();
-        handle
-            .register_syncdb("extra", SigLevel::USE_DEFAULT)
-            .unwrap();
-        handle
-            .register_syncdb("community", SigLevel::USE_DEFAULT)
-            .unwrap();
-
-        Ok(Self { handle })
-    }
-
-    fn find_package(&self, package_name: &str) -> Result<&alpm::Package, alpm::Error> {
+    pub fn new(
+        root: &Path,
+        dbpath: &Path,
+        cache_dirs: Vec<PathBuf>,
+        config: &PacmanConfig,
+    ) -> Result<Self, alpm::Error> {
+        let mut handle = Alpm::new(root.as_os_str().as_bytes(), dbpath.as_os_str().as_bytes())?;
+        for arch in &config.architectures {
+            handle.add_architecture(arch.as_str())?;
+        }
+        for repo in &config.repos {
+            let sig_level = repo
+                .sig_level
+                .or(config.sig_level)
+                .unwrap_or(SigLevel::USE_DEFAULT);
+            let db = handle.register_syncdb_mut(repo.name.as_str(), sig_level)?;
+            for server in &repo.servers {
+                db.add_server(server.as_str())?;
+            }
+        }
+
+        Ok(Self {
+            handle,
+            dbpath: dbpath.to_path_buf(),
+            cache_dirs,
+        })
+    }
+
+    /// When the databases the handle was opened on were last written
+    pub fn db_stamps(&self) -> DbStamps {
+        DbStamps::read(&self.dbpath)
+    }
+
+    /// Installed packages are looked up in the local database so that install date,
+    /// reason and version reflect what is on disk. Sync databases are only consulted for
+    /// packages that are not installed.
+    fn find_package(
+        &self,
+        package_name: &str,
+    ) -> Result<(&alpm::Package, PackageSource), alpm::Error> {
+        if let Ok(pkg) = self.handle.localdb().pkg(package_name) {
+            return Ok((pkg, PackageSource::Local));
+        }
         for db in self.handle.syncdbs() {
             if let Ok(pkg) = db.pkg(package_name) {
-                return Ok(pkg);
+                return Ok((pkg, PackageSource::Sync(db.name().to_string())));
             }
         }
 
@@ -58,56 +184,145 @@ impl AlpmService {
     }
 
     pub fn package_info(&self, package_name: &str) -> Result<PackageInfo, alpm::Error> {
-        let pkg = self.find_package(package_name)?;
-        Ok(PackageInfo {
-            version: pkg.version().to_string(),
-            description: pkg.desc().unwrap_or("None").to_string(),
-            arch: pkg.arch().map(|arch| arch.to_string()),
-            url: pkg.url().map(|url| url.to_string()),
-            licenses: pkg
-                .licenses()
-                .iter()
-                .map(|license| license.to_string())
-                .collect(),
-            groups: pkg.groups().iter().map(|group| group.to_string()).collect(),
-            provides: pkg
-                .provides()
-                .iter()
-                .map(|provide| provide.to_string())
-                .collect(),
-            conflics_with: pkg
-                .conflicts()
-                .iter()
-                .map(|conflicts_with| conflicts_with.to_string())
-                .collect(),
-            replaces: pkg
-                .replaces()
-                .iter()
-                .map(|replaces| replaces.to_string())
-                .collect(),
-            installed_size: pkg.size(),
-            packager: pkg.packager().map(|packager| packager.to_string()),
-            build_date: pkg.build_date(),
-            install_date: pkg.install_date(),
-            install_reason: pkg.reason(),
-            install_script: pkg.has_scriptlet(),
-            validated_by: pkg.validation(),
-        })
+        match self.find_package(package_name) {
+            Ok((pkg, source)) => Ok(package_info(pkg, source)),
+            Err(_) => self.cached_package_info(package_name),
+        }
     }
 
+    /// Reads the newest version of a package from the package cache
+    fn cached_package_info(&self, package_name: &str) -> Result<PackageInfo, alpm::Error> {
+        let newest = pkgcache::find_cached(&self.cache_dirs, package_name)
+            .into_iter()
+            .max_by(|a, b| alpm::vercmp(a.version.as_str(), b.version.as_str()))
+            .ok_or(alpm::Error::PkgNotFound)?;
+        let pkg =
+            self.handle
+                .pkg_load(newest.path.as_os_str().as_bytes(), false, SigLevel::NONE)?;
+        Ok(package_info(&pkg, PackageSource::Cache(newest.path)))
+    }
+
+    /// Versions of all packages in the local database
+    pub fn installed_packages(&self) -> PackageSet {
+        self.handle
+            .localdb()
+            .pkgs()
+            .iter()
+            .map(|pkg| (pkg.name().to_string(), pkg.version().to_string()))
+            .collect()
+    }
+
+    /// Every installed package with why it was installed and whether anything needs it
+    pub fn local_packages(&self) -> Vec<InstalledPackage> {
+        let mut packages: Vec<InstalledPackage> = self
+            .handle
+            .localdb()
+            .pkgs()
+            .iter()
+            .map(|pkg| InstalledPackage {
+                name: pkg.name().to_string(),
+                version: pkg.version().to_string(),
+                explicit: pkg.reason() == PackageReason::Explicit,
+                required: !pkg.required_by().is_empty() || !pkg.optional_for().is_empty(),
+            })
+            .collect();
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+        packages
+    }
+
+    /// Change in installed size for each change, where both versions can be found in the
+    /// local database, a sync database or the package cache
+    pub fn size_deltas(&self, changes: &[PackageDiff]) -> Vec<Option<i64>> {
+        let cached = self.scan_cache();
+        let size = |package: &str, version: &str| self.installed_size(package, version, &cached);
+        changes
+            .iter()
+            .map(|change| match change {
+                PackageDiff::Added { package, version } => size(package, version),
+                PackageDiff::Removed { package, version } => size(package, version).map(|s| -s),
+                PackageDiff::Upgraded {
+                    package,
+                    old_version,
+                    new_version,
+                }
+                | PackageDiff::Downgraded {
+                    package,
+                    old_version,
+                    new_version,
+                } => Some(size(package, new_version)? - size(package, old_version)?),
+            })
+            .collect()
+    }
+
+    fn installed_size(
+        &self,
+        package: &str,
+        version: &str,
+        cached: &[CachedPackage],
+    ) -> Option<i64> {
+        let dbs = std::iter::once(self.handle.localdb()).chain(self.handle.syncdbs());
+        for db in dbs {
+            if let Ok(pkg) = db.pkg(package)
+                && pkg.version().as_str() == version
+            {
+                return Some(pkg.isize());
+            }
+        }
+        let file = cached
+            .iter()
+            .find(|cached| cached.name == package && cached.version == version)?;
+        let pkg = self
+            .handle
+            .pkg_load(file.path.as_os_str().as_bytes(), false, SigLevel::NONE)
+            .ok()?;
+        Some(pkg.isize())
+    }
+
+    /// Every package file in the cache directories
+    pub fn scan_cache(&self) -> Vec<CachedPackage> {
+        pkgcache::scan(&self.cache_dirs)
+    }
+
+    /// Finds what satisfies `requirement`, installed packages first, resolving provides
+    fn resolve(&self, spec: String, requirement: &str) -> Dependency {
+        let (satisfier, resolution) =
+            if let Some(pkg) = self.handle.localdb().pkgs().find_satisfier(requirement) {
+                (Some(pkg.name().to_string()), Resolution::Installed)
+            } else if let Some(pkg) = self.handle.syncdbs().find_satisfier(requirement) {
+                (Some(pkg.name().to_string()), Resolution::Available)
+            } else {
+                (None, Resolution::Missing)
+            };
+        Dependency {
+            spec,
+            satisfier,
+            resolution,
+        }
+    }
+
+    /// Forward and reverse dependencies, each resolved to the package satisfying it
     pub fn dependencies(&self, package_name: &str) -> Result<Dependencies, alpm::Error> {
-        let pkg = self.find_package(package_name)?;
-        let depends_on = pkg.depends().iter().map(|dep| dep.to_string()).collect();
-        let optional_deps = pkg.optdepends().iter().map(|dep| dep.to_string()).collect();
+        let (pkg, _) = self.find_package(package_name)?;
+        let depends_on = pkg
+            .depends()
+            .iter()
+            .map(|dep| self.resolve(dep.to_string(), &dep.to_string()))
+            .collect();
+        // The full string of an optional dependency includes its description
+        let optional_deps = pkg
+            .optdepends()
+            .iter()
+            .map(|dep| self.resolve(dep.to_string(), dep.name()))
+            .collect();
         let required_by = pkg
             .required_by()
             .iter()
-            .map(|dep| dep.to_string())
+            .map(|name| self.resolve(name.to_string(), name))
             .collect();
         let optional_for = pkg
             .optional_for()
             .iter()
-            .map(|dep| dep.to_string())
+            .map(|name| self.resolve(name.to_string(), name))
             .collect();
         Ok(Dependencies {
             depends_on,
@@ -117,3 +332,41 @@ impl AlpmService {
         })
     }
 }
+
+fn package_info(pkg: &alpm::Pkg, source: PackageSource) -> PackageInfo {
+    PackageInfo {
+        source,
+        version: pkg.version().to_string(),
+        description: pkg.desc().unwrap_or("None").to_string(),
+        arch: pkg.arch().map(|arch| arch.to_string()),
+        url: pkg.url().map(|url| url.to_string()),
+        licenses: pkg
+            .licenses()
+            .iter()
+            .map(|license| license.to_string())
+            .collect(),
+        groups: pkg.groups().iter().map(|group| group.to_string()).collect(),
+        provides: pkg
+            .provides()
+            .iter()
+            .map(|provide| provide.to_string())
+            .collect(),
+        conflics_with: pkg
+            .conflicts()
+            .iter()
+            .map(|conflicts_with| conflicts_with.to_string())
+            .collect(),
+        replaces: pkg
+            .replaces()
+            .iter()
+            .map(|replaces| replaces.to_string())
+            .collect(),
+        installed_size: pkg.isize(),
+        packager: pkg.packager().map(|packager| packager.to_string()),
+        build_date: pkg.build_date(),
+        install_date: pkg.install_date(),
+        install_reason: pkg.reason(),
+        install_script: pkg.has_scriptlet(),
+        validated_by: pkg.validation(),
+    }
+}
@@ -1,36 +1,202 @@
 use ratatui::DefaultTerminal;
+use ratatui::widgets::ListState;
 use std::io;
+use std::sync::mpsc::Receiver;
 
 use crate::event;
 use crate::message::Message;
-use crate::model::{Model, RunningState};
+use crate::model::{Model, PackagePane, RunningState, Screen};
+use crate::stats::Stats;
 use crate::view;
 
-pub fn run(mut model: Model, terminal: &mut DefaultTerminal) -> io::Result<()> {
+/// Runs until quit, handling messages from input, the tick timer and background workers
+/// as they arrive
+pub fn run(
+    mut model: Model,
+    terminal: &mut DefaultTerminal,
+    messages: Receiver<Message>,
+) -> io::Result<()> {
     while model.running_state != RunningState::Done {
-        terminal.draw(|frame| view::view(&mut model, frame))?;
-
-        if let Some(msg) = event::handle_event(&model)? {
-            process_message(&mut model, msg);
+        if model.redraw {
+            terminal.draw(|frame| view::view(&mut model, frame))?;
+            model.redraw = false;
         }
+
+        // All senders gone means there is nothing left that could wake us up
+        let Ok(msg) = messages.recv() else {
+            break;
+        };
+        process_message(&mut model, msg);
     }
     Ok(())
 }
 
 fn process_message(model: &mut Model, msg: Message) {
+    // Ticks decide for themselves whether they changed anything
+    if !matches!(msg, Message::Tick) {
+        model.redraw = true;
+    }
     let mut current_msg = Some(msg);
     while let Some(msg) = current_msg {
         current_msg = update(model, msg);
     }
+    model.request_package_info();
+    model.request_dependencies();
 }
 
 fn update(model: &mut Model, msg: Message) -> Option<Message> {
     match msg {
-        Message::MoveUp => {
-            model.select_previous();
+        Message::Key(key) => {
+            // Notices stay up until the next key press
+            model.notice = None;
+            return event::handle_key(model, key);
+        }
+        Message::Resize => {}
+        Message::Tick => {
+            model.ticks = model.ticks.wrapping_add(1);
+            // Keep the loading indicator spinning
+            model.redraw |= model.loading;
+        }
+        Message::LogsLoaded(logs, following) => {
+            model.set_logs(*logs);
+            model.following = following;
+        }
+        Message::LogAppended(tail) => model.append_logs(*tail),
+        Message::FollowFailed(error) => {
+            model.following = false;
+            model.follow_error = Some(error);
+        }
+        Message::LoadFailed(error) => {
+            model.loading = false;
+            model.load_error = Some(error);
+        }
+        Message::PackageInfoLoaded(package, info) => model.set_package_info(package, info),
+        Message::DatabasesChanged(change) => model.databases_changed(change),
+        Message::DependenciesLoaded(package, dependencies) => {
+            model.set_dependencies(package, dependencies)
+        }
+        Message::RollbackPlanned(plan) => model.set_rollback(*plan),
+        Message::LocalPackagesLoaded(packages) => model.set_local_packages(packages),
+        Message::ComparisonSized(comparison) => model.set_comparison(*comparison),
+        Message::MoveUp => match model.screens.last_mut() {
+            Some(Screen::Main) => model.select_previous(),
+            Some(Screen::Package {
+                focus: PackagePane::Details,
+                scroll,
+                ..
+            }) => *scroll = scroll.saturating_sub(1),
+            Some(screen) => {
+                if let Some(state) = screen.list_state() {
+                    state.select_previous();
+                }
+            }
+            None => {}
+        },
+        Message::MoveDown => match model.screens.last_mut() {
+            Some(Screen::Main) => model.select_next(),
+            // Kept within the details when they are drawn
+            Some(Screen::Package {
+                focus: PackagePane::Details,
+                scroll,
+                ..
+            }) => *scroll = scroll.saturating_add(1),
+            Some(screen) => {
+                if let Some(state) = screen.list_state() {
+                    state.select_next();
+                }
+            }
+            None => {}
+        },
+        Message::ShowDiagnostics => {
+            model.push_screen(Screen::Diagnostics(
+                ListState::default().with_selected(Some(0)),
+            ));
+        }
+        Message::ShowHistory => model.show_history(),
+        Message::ShowPackage => model.show_package(),
+        Message::ToggleDependency => model.toggle_dependency(),
+        Message::OpenDependency => model.open_dependency(),
+        Message::SwitchPane => model.switch_package_pane(),
+        Message::ShowPackages => model.show_packages(),
+        Message::SwitchView => model.switch_package_view(),
+        Message::ShowStats => {
+            // Computed afresh as following may have added events since the last time
+            model.push_screen(Screen::Stats(Box::new(Stats::new(&model.logs))));
+        }
+        Message::ShowHelp => model.push_screen(Screen::Help),
+        Message::ShowRollback => model.plan_rollback(),
+        Message::MarkCompare => model.mark_compare(),
+        Message::StartDateMark => {
+            model.date_prompt = Some(String::new());
+        }
+        Message::DateInput(c) => {
+            if let Some(date) = &mut model.date_prompt {
+                date.push(c);
+            }
+        }
+        Message::DateBackspace => {
+            if let Some(date) = &mut model.date_prompt {
+                date.pop();
+            }
+        }
+        Message::ConfirmDate => model.mark_date(),
+        Message::CancelDate => {
+            model.date_prompt = None;
+        }
+        Message::WriteRollbackScript => {
+            model.notice = match model.write_rollback_script() {
+                Ok(Some(path)) => Some(format!("Wrote {}", path.display())),
+                Ok(None) => None,
+                Err(error) => Some(format!("Writing the script failed: {}", error)),
+            };
+        }
+        Message::StartFilter => {
+            model.filter_editing = true;
+        }
+        Message::FilterInput(c) => {
+            let query = format!("{}{}", model.filter.query, c);
+            model.set_filter(&query);
+        }
+        Message::FilterBackspace => {
+            let mut query = model.filter.query.clone();
+            query.pop();
+            model.set_filter(&query);
+        }
+        Message::ConfirmFilter => {
+            model.filter_editing = false;
+        }
+        Message::CancelFilter => {
+            model.filter_editing = false;
+            model.set_filter("");
+        }
+        Message::NextMatch => model.select_next_match(),
+        Message::PreviousMatch => model.select_previous_match(),
+        Message::StartExport => {
+            model.export_prompt = true;
+        }
+        Message::Export(format) => {
+            model.export_prompt = false;
+            let path = model.export_visible(format);
+            model.notice = Some(format!(
+                "Exporting {} events to {}…",
+                model.event_count(),
+                path.display()
+            ));
+        }
+        Message::Exported(path, count) => {
+            model.notice = Some(format!("Exported {} events to {}", count, path.display()));
+        }
+        Message::ExportFailed(error) => {
+            model.notice = Some(format!("Export failed: {}", error));
+        }
+        Message::CancelExport => {
+            model.export_prompt = false;
         }
-        Message::MoveDown => {
-            model.select_next();
+        Message::Back => {
+            if matches!(model.screen(), Screen::Main) && !model.filter.query.is_empty() {
+                model.set_filter("");
+            }
+            model.pop_screen();
         }
         Message::Quit => {
             // You can handle cleanup and exit here
@@ -0,0 +1,273 @@
+//! Command line arguments
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use clap::{CommandFactory, Parser, Subcommand, error::ErrorKind};
+
+use crate::compare::{self, Point};
+use crate::export::ExportFormat;
+use crate::filter;
+use crate::logparse::LogKey;
+use crate::pacmanconf::PacmanConfig;
+
+/// Browse the pacman transaction history
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Args {
+    /// Path to pacman.log [default: <ROOT>/var/log/pacman.log]
+    #[arg(long, value_name = "FILE")]
+    log: Option<PathBuf>,
+
+    /// Installation root [default: /]
+    #[arg(long, value_name = "DIR")]
+    root: Option<PathBuf>,
+
+    /// Path to the pacman databases [default: <ROOT>/var/lib/pacman]
+    #[arg(long, value_name = "DIR")]
+    dbpath: Option<PathBuf>,
+
+    /// Path to pacman.conf [default: /etc/pacman.conf]
+    #[arg(long, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Inspect a mounted guest system such as a chroot or rescue disk. All other paths
+    /// are relative to it.
+    #[arg(long, value_name = "DIR")]
+    sysroot: Option<PathBuf>,
+
+    /// Abort on the first log line that fails to parse
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Keep reading the log as pacman appends to it
+    #[arg(short, long)]
+    pub follow: bool,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Prints plain text for scripts instead of starting the TUI
+#[derive(Debug, PartialEq, Subcommand)]
+pub enum Command {
+    /// List all transactions, oldest first
+    List,
+    /// List every event for a package
+    History { package: String },
+    /// Show a transaction by the id printed by `list`
+    Show { id: LogKey },
+    /// List package changes since a date (YYYY-MM-DD, UTC)
+    Since {
+        #[arg(value_parser = filter::parse_date)]
+        date: i64,
+    },
+    /// List the packages installed at a time, as replayed from the log on top of those
+    /// installed now that it never mentions
+    State {
+        /// A date (YYYY-MM-DD) or date and time (YYYY-MM-DD HH:MM), in UTC
+        #[arg(value_parser = filter::parse_time)]
+        time: i64,
+        /// Instead list what changed between then and the packages installed now
+        #[arg(long)]
+        diff: bool,
+    },
+    /// Show the net change in packages between two points, each a transaction id as
+    /// printed by `list` or a date or time as for `state`. Both transactions are included.
+    Compare {
+        #[arg(value_parser = compare::parse_point)]
+        from: Point,
+        #[arg(value_parser = compare::parse_point)]
+        to: Point,
+    },
+    /// Show what the package databases know about a package
+    Info { package: String },
+    /// Print a script that rolls back a transaction using the package cache, without
+    /// running anything
+    Rollback { id: LogKey },
+    /// Export events and their transactions
+    Export {
+        #[arg(long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// Only export events matching a filter, as typed after / in the TUI
+        query: Vec<String>,
+    },
+}
+
+// Paths given on the command line take precedence over pacman.conf
+impl Args {
+    /// Parses the command line, exiting with usage on errors
+    pub fn parse_checked() -> Self {
+        Self::try_parse_checked(std::env::args_os()).unwrap_or_else(|error| error.exit())
+    }
+
+    // clap cannot make an argument conflict with subcommands, so that is checked here
+    fn try_parse_checked<I, T>(args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let args = Args::try_parse_from(args)?;
+        if args.follow && args.command.is_some() {
+            return Err(Args::command().error(
+                ErrorKind::ArgumentConflict,
+                "--follow cannot be used with a subcommand, which prints once and exits",
+            ));
+        }
+        Ok(args)
+    }
+
+    pub fn root(&self, config: &PacmanConfig) -> PathBuf {
+        let root = self.root.as_ref().or(config.root_dir.as_ref());
+        self.in_sysroot(root.map_or(Path::new("/"), |root| root.as_path()))
+    }
+
+    // Like pacman, the database and log default to locations inside the root
+    pub fn dbpath(&self, config: &PacmanConfig) -> PathBuf {
+        match self.dbpath.as_ref().or(config.db_path.as_ref()) {
+            Some(dbpath) => self.in_sysroot(dbpath),
+            None => self.root(config).join("var/lib/pacman"),
+        }
+    }
+
+    pub fn log(&self, config: &PacmanConfig) -> PathBuf {
+        match self.log.as_ref().or(config.log_file.as_ref()) {
+            Some(log) => self.in_sysroot(log),
+            None => self.root(config).join("var/log/pacman.log"),
+        }
+    }
+
+    pub fn cache_dirs(&self, config: &PacmanConfig) -> Vec<PathBuf> {
+        if config.cache_dirs.is_empty() {
+            vec![self.in_sysroot(Path::new("/var/cache/pacman/pkg"))]
+        } else {
+            config
+                .cache_dirs
+                .iter()
+                .map(|dir| self.in_sysroot(dir))
+                .collect()
+        }
+    }
+
+    pub fn config(&self) -> PathBuf {
+        self.in_sysroot(
+            self.config
+                .as_deref()
+                .unwrap_or(Path::new("/etc/pacman.conf")),
+        )
+    }
+
+    pub fn in_sysroot(&self, path: &Path) -> PathBuf {
+        match &self.sysroot {
+            Some(sysroot) => sysroot.join(path.strip_prefix("/").unwrap_or(path)),
+            None => path.to_path_buf(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let args = Args::parse_from(["puckrat"]);
+        let config = PacmanConfig::default();
+        assert_eq!(args.root(&config), Path::new("/"));
+        assert_eq!(args.dbpath(&config), Path::new("/var/lib/pacman"));
+        assert_eq!(args.log(&config), Path::new("/var/log/pacman.log"));
+        assert_eq!(args.config(), Path::new("/etc/pacman.conf"));
+    }
+
+    #[test]
+    fn test_root() {
+        let args = Args::parse_from(["puckrat", "--root", "/mnt", "--log", "/tmp/pacman.log"]);
+        let config = PacmanConfig::default();
+        assert_eq!(args.dbpath(&config), Path::new("/mnt/var/lib/pacman"));
+        assert_eq!(args.log(&config), Path::new("/tmp/pacman.log"));
+        assert_eq!(args.config(), Path::new("/etc/pacman.conf"));
+    }
+
+    #[test]
+    fn test_sysroot() {
+        let args = Args::parse_from(["puckrat", "--sysroot", "/mnt", "--dbpath", "/db"]);
+        let config = PacmanConfig::default();
+        assert_eq!(args.root(&config), Path::new("/mnt"));
+        assert_eq!(args.dbpath(&config), Path::new("/mnt/db"));
+        assert_eq!(args.log(&config), Path::new("/mnt/var/log/pacman.log"));
+        assert_eq!(args.config(), Path::new("/mnt/etc/pacman.conf"));
+    }
+
+    #[test]
+    fn test_config_precedence() {
+        let args = Args::parse_from(["puckrat", "--dbpath", "/db"]);
+        let config = PacmanConfig {
+            db_path: Some(PathBuf::from("/conf/db")),
+            log_file: Some(PathBuf::from("/conf/pacman.log")),
+            ..Default::default()
+        };
+        assert_eq!(args.dbpath(&config), Path::new("/db"));
+        assert_eq!(args.log(&config), Path::new("/conf/pacman.log"));
+    }
+
+    #[test]
+    fn test_command() {
+        assert_eq!(Args::parse_from(["puckrat"]).command, None);
+        let args = Args::parse_from([
+            "puckrat",
+            "--log",
+            "/tmp/pacman.log",
+            "show",
+            "1769990400.3",
+        ]);
+        assert_eq!(
+            args.command,
+            Some(Command::Show {
+                id: LogKey {
+                    timestamp: 1769990400,
+                    offset: 3
+                }
+            })
+        );
+        assert!(Args::try_parse_from(["puckrat", "show", "3rd"]).is_err());
+        let conflict = Args::try_parse_checked(["puckrat", "--follow", "list"]).unwrap_err();
+        assert_eq!(conflict.kind(), ErrorKind::ArgumentConflict);
+        assert!(Args::try_parse_checked(["puckrat", "--follow"]).is_ok_and(|args| args.follow));
+        let args = Args::parse_from(["puckrat", "since", "2026-01-02"]);
+        assert_eq!(args.command, Some(Command::Since { date: 1767312000 }));
+        assert!(Args::try_parse_from(["puckrat", "since", "yesterday"]).is_err());
+        let args = Args::parse_from(["puckrat", "compare", "1767225600", "2026-01-02"]);
+        assert_eq!(
+            args.command,
+            Some(Command::Compare {
+                from: Point::Transaction(LogKey {
+                    timestamp: 1767225600,
+                    offset: 0
+                }),
+                to: Point::Time(1767312000)
+            })
+        );
+        let args = Args::parse_from(["puckrat", "state", "--diff", "2026-01-02 12:30"]);
+        assert_eq!(
+            args.command,
+            Some(Command::State {
+                time: 1767357000,
+                diff: true
+            })
+        );
+        let args = Args::parse_from([
+            "puckrat",
+            "export",
+            "--format",
+            "csv",
+            "type:removed",
+            "lib",
+        ]);
+        assert_eq!(
+            args.command,
+            Some(Command::Export {
+                format: ExportFormat::Csv,
+                query: vec!["type:removed".to_string(), "lib".to_string()],
+            })
+        );
+    }
+}
@@ -0,0 +1,539 @@
+//! Non-interactive subcommands for shell scripts, cron jobs and SSH sessions. Output is
+//! plain text with one record per line and tab separated fields.
+use std::io::{self, Write};
+
+use crate::alpmutil::AlpmService;
+use crate::cli::Command;
+use crate::compare::{self, Comparison, Point};
+use crate::export::{self, ExportError, ExportFormat};
+use crate::filter::Filter;
+use crate::format::{render_size, render_timestamp};
+use crate::logparse::{LogDB, LogEvent, LogKey, Transaction};
+use crate::pkgcache::CachedPackage;
+use crate::rollback::RollbackPlan;
+use crate::snapshot::{self, PackageDiff, PackageSet};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CommandError {
+    #[error("No transaction with id {0}, see `puckrat list`")]
+    UnknownTransaction(LogKey),
+
+    #[error("{0} does not appear in the log and is not known to any database")]
+    UnknownPackage(String),
+
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(String),
+
+    #[error(transparent)]
+    Export(#[from] ExportError),
+
+    #[error("Failed to open the package databases: {0}")]
+    Alpm(#[from] alpm::Error),
+
+    #[error(transparent)]
+    Io(#[from] io::Error),
+}
+
+/// Runs a subcommand, writing to stdout. The databases are only opened by commands that
+/// need them.
+pub fn run(
+    command: &Command,
+    logs: &LogDB,
+    open_alpm: &dyn Fn() -> Result<AlpmService, alpm::Error>,
+) -> Result<(), CommandError> {
+    let mut out = io::stdout().lock();
+    let result = match command {
+        Command::List => list(logs, &mut out),
+        Command::History { package } => history(logs, package, &mut out),
+        Command::Show { id } => show(logs, id, &mut out),
+        Command::Since { date } => since(logs, *date, &mut out),
+        Command::State { time, diff } => {
+            let installed = open_alpm()?.installed_packages();
+            if *diff {
+                state_diff(logs, *time, &installed, &mut out)
+            } else {
+                state(logs, *time, &installed, &mut out)
+            }
+        }
+        Command::Compare { from, to } => compare(&open_alpm()?, logs, from, to, &mut out),
+        Command::Info { package } => info(&open_alpm()?, logs, package, &mut out),
+        Command::Rollback { id } => rollback(logs, id, &open_alpm()?.scan_cache(), &mut out),
+        Command::Export { format, query } => export(logs, *format, &query.join(" "), &mut out),
+    };
+    match result.and_then(|()| Ok(out.flush()?)) {
+        // Reading only the start of the output, as `head` does, is not an error
+        Err(CommandError::Io(error) | CommandError::Export(ExportError::Io(error)))
+            if error.kind() == io::ErrorKind::BrokenPipe =>
+        {
+            Ok(())
+        }
+        result => result,
+    }
+}
+
+/// id, start, status, number of package changes and command of every transaction
+fn list(logs: &LogDB, out: &mut impl Write) -> Result<(), CommandError> {
+    for (key, tx) in &logs.transactions {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}",
+            key,
+            render_timestamp(tx.start),
+            status(tx),
+            tx.packages.len(),
+            tx.command.as_deref().unwrap_or("-")
+        )?;
+    }
+    Ok(())
+}
+
+/// time, kind and version of every event for a package. Times are those of the
+/// transaction the event belongs to.
+fn history(logs: &LogDB, package: &str, out: &mut impl Write) -> Result<(), CommandError> {
+    let history = logs.package_history(package);
+    if history.is_empty() {
+        return Err(CommandError::UnknownPackage(package.to_string()));
+    }
+    for (key, event) in history {
+        writeln!(
+            out,
+            "{}\t{}\t{}",
+            render_timestamp(key.timestamp),
+            event.kind(),
+            versions(event)
+        )?;
+    }
+    Ok(())
+}
+
+/// Transactions are identified by their keys, as printed by `list`
+fn transaction<'a>(logs: &'a LogDB, id: &LogKey) -> Result<&'a Transaction, CommandError> {
+    logs.transactions
+        .get(id)
+        .ok_or_else(|| CommandError::UnknownTransaction(id.clone()))
+}
+
+fn show(logs: &LogDB, id: &LogKey, out: &mut impl Write) -> Result<(), CommandError> {
+    let tx = transaction(logs, id)?;
+    writeln!(out, "id\t{}", id)?;
+    writeln!(out, "command\t{}", tx.command.as_deref().unwrap_or("-"))?;
+    writeln!(out, "start\t{}", render_timestamp(tx.start))?;
+    writeln!(out, "end\t{}", render_timestamp(tx.end))?;
+    writeln!(out, "status\t{}", status(tx))?;
+    for key in &tx.packages {
+        package_line(logs, key, out)?;
+    }
+    for hook in &tx.hooks {
+        writeln!(out, "hook\t{}", hook)?;
+    }
+    for output in &tx.scriptlet_output {
+        writeln!(out, "scriptlet\t{}", output)?;
+    }
+    for warning in &tx.warnings {
+        writeln!(out, "warning\t{}", warning)?;
+    }
+    for error in &tx.errors {
+        writeln!(out, "error\t{}", error)?;
+    }
+    Ok(())
+}
+
+/// Every package change from midnight of `date` on
+fn since(logs: &LogDB, date: i64, out: &mut impl Write) -> Result<(), CommandError> {
+    let start = LogKey {
+        timestamp: date,
+        offset: 0,
+    };
+    for (key, event) in logs.events.range(start..) {
+        if event.package().is_some() {
+            write!(out, "{}\t", render_timestamp(key.timestamp))?;
+            package_line(logs, key, out)?;
+        }
+    }
+    Ok(())
+}
+
+/// name and version of every package installed at `time`. Packages in `installed` that
+/// never appear in the log were installed all along.
+fn state(
+    logs: &LogDB,
+    time: i64,
+    installed: &PackageSet,
+    out: &mut impl Write,
+) -> Result<(), CommandError> {
+    let mut then = snapshot::installed_at(logs, time);
+    snapshot::add_unlogged(&mut then, logs, installed);
+    for (package, version) in then {
+        writeln!(out, "{}\t{}", package, version)?;
+    }
+    Ok(())
+}
+
+/// How the packages installed at `time` differ from those `installed` now
+fn state_diff(
+    logs: &LogDB,
+    time: i64,
+    installed: &PackageSet,
+    out: &mut impl Write,
+) -> Result<(), CommandError> {
+    let mut then = snapshot::installed_at(logs, time);
+    snapshot::add_unlogged(&mut then, logs, installed);
+    for change in snapshot::diff(&then, installed) {
+        diff_line(&change, out)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// The net change between two points, with the change in installed size in bytes for
+/// each package. The counts and total size go to stderr.
+fn compare(
+    alpm: &AlpmService,
+    logs: &LogDB,
+    from: &Point,
+    to: &Point,
+    out: &mut impl Write,
+) -> Result<(), CommandError> {
+    let (mut from_key, mut to_key) = (bound(logs, from, false)?, bound(logs, to, true)?);
+    if from_key > to_key {
+        (from_key, to_key) = (bound(logs, to, false)?, bound(logs, from, true)?);
+    }
+    let mut comparison = Comparison::new(logs, from_key, to_key);
+    let deltas = alpm.size_deltas(&comparison.changes);
+    for (change, delta) in comparison.changes.iter().zip(&deltas) {
+        diff_line(change, out)?;
+        match delta {
+            Some(delta) => writeln!(out, "\t{}", delta)?,
+            None => writeln!(out, "\t-")?,
+        }
+    }
+    comparison.size_deltas = Some(deltas);
+
+    let counts = comparison.counts();
+    let (total, unknown) = comparison.size_delta().unwrap_or_default();
+    eprintln!(
+        "{} added, {} removed, {} upgraded, {} downgraded, installed size {}{}",
+        counts.added,
+        counts.removed,
+        counts.upgraded,
+        counts.downgraded,
+        render_size(total),
+        if unknown > 0 {
+            format!(" ({} packages of unknown size)", unknown)
+        } else {
+            String::new()
+        }
+    );
+    Ok(())
+}
+
+/// Where a point is in the log. A transaction is taken before it starts at the start of
+/// a comparison and after it ends at the end, so that both ends are included.
+fn bound(logs: &LogDB, point: &Point, end: bool) -> Result<LogKey, CommandError> {
+    Ok(match point {
+        Point::Transaction(id) if end => compare::after(logs, transaction(logs, id)?),
+        Point::Transaction(id) => compare::before(transaction(logs, id)?),
+        Point::Time(time) => compare::at(*time),
+    })
+}
+
+/// Prints the script, warning on stderr about package files missing from the cache
+fn rollback(
+    logs: &LogDB,
+    id: &LogKey,
+    cached: &[CachedPackage],
+    out: &mut impl Write,
+) -> Result<(), CommandError> {
+    let mut plan = RollbackPlan::new(logs, transaction(logs, id)?);
+    plan.locate(cached);
+    for (package, version) in plan.missing() {
+        eprintln!(
+            "warning: {} {} is not in the package cache",
+            package, version
+        );
+    }
+    write!(out, "{}", plan.script())?;
+    Ok(())
+}
+
+fn export(
+    logs: &LogDB,
+    format: ExportFormat,
+    query: &str,
+    out: &mut impl Write,
+) -> Result<(), CommandError> {
+    let filter = Filter::new(query);
+    if let Some(error) = filter.error {
+        return Err(CommandError::InvalidFilter(error));
+    }
+    let keys: Vec<LogKey> = logs
+        .events
+        .iter()
+        .filter(|(key, event)| filter.matches(key, event))
+        .map(|(key, _)| key.clone())
+        .collect();
+    Ok(export::export(logs, &keys, format, out)?)
+}
+
+/// The package as found by alpm, or failing that, as recorded in the log
+fn info(
+    alpm: &AlpmService,
+    logs: &LogDB,
+    package: &str,
+    out: &mut impl Write,
+) -> Result<(), CommandError> {
+    if let Ok(info) = alpm.package_info(package) {
+        writeln!(out, "name\t{}", package)?;
+        writeln!(out, "source\t{}", info.source)?;
+        writeln!(out, "version\t{}", info.version)?;
+        writeln!(out, "description\t{}", info.description)?;
+        writeln!(out, "architecture\t{}", info.arch.as_deref().unwrap_or("-"))?;
+        writeln!(out, "url\t{}", info.url.as_deref().unwrap_or("-"))?;
+        writeln!(out, "licenses\t{}", info.licenses.join(" "))?;
+    } else if let Some(summary) = logs.package_summary(package) {
+        writeln!(out, "name\t{}", package)?;
+        writeln!(out, "source\tpacman.log")?;
+        writeln!(out, "version\t{}", summary.last_version)?;
+        if let Some(first_installed) = summary.first_installed {
+            writeln!(
+                out,
+                "first installed\t{}",
+                render_timestamp(first_installed)
+            )?;
+        }
+        writeln!(
+            out,
+            "last changed\t{}",
+            render_timestamp(summary.last_event)
+        )?;
+        if let Some(removed) = summary.removed {
+            writeln!(out, "removed\t{}", render_timestamp(removed))?;
+        }
+    } else {
+        return Err(CommandError::UnknownPackage(package.to_string()));
+    }
+    Ok(())
+}
+
+fn package_line(logs: &LogDB, key: &LogKey, out: &mut impl Write) -> io::Result<()> {
+    let Some(event) = logs.events.get(key) else {
+        return Ok(());
+    };
+    writeln!(
+        out,
+        "{}\t{}\t{}",
+        event.kind(),
+        event.package().unwrap_or_default(),
+        versions(event)
+    )
+}
+
+/// kind, package and versions of a change, without a line break
+fn diff_line(change: &PackageDiff, out: &mut impl Write) -> io::Result<()> {
+    match change {
+        PackageDiff::Added { package, version } => write!(out, "added\t{}\t{}", package, version),
+        PackageDiff::Removed { package, version } => {
+            write!(out, "removed\t{}\t{}", package, version)
+        }
+        PackageDiff::Upgraded {
+            package,
+            old_version,
+            new_version,
+        } => write!(
+            out,
+            "upgraded\t{}\t{} -> {}",
+            package, old_version, new_version
+        ),
+        PackageDiff::Downgraded {
+            package,
+            old_version,
+            new_version,
+        } => write!(
+            out,
+            "downgraded\t{}\t{} -> {}",
+            package, old_version, new_version
+        ),
+    }
+}
+
+fn status(tx: &Transaction) -> &'static str {
+    if tx.implicit {
+        "implicit"
+    } else {
+        tx.status.name()
+    }
+}
+
+fn versions(event: &LogEvent) -> String {
+    match event {
+        LogEvent::Installed { version, .. }
+        | LogEvent::Reinstalled { version, .. }
+        | LogEvent::Removed { version, .. } => version.clone(),
+        LogEvent::Upgraded {
+            old_version,
+            new_version,
+            ..
+        }
+        | LogEvent::Downgraded {
+            old_version,
+            new_version,
+            ..
+        } => format!("{} -> {}", old_version, new_version),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logparse::parse_fixture;
+
+    fn logs() -> LogDB {
+        let content = "\
+[2026-01-28T19:36:35+0000] [PACMAN] Running 'pacman -S gelly'
+[2026-01-28T19:36:50+0000] [ALPM] transaction started
+[2026-01-28T19:36:50+0000] [ALPM] installed gelly (4.0.6-10)
+[2026-01-28T19:36:51+0000] [ALPM] transaction completed
+[2026-01-28T19:36:52+0000] [ALPM] running '30-systemd-update.hook'...
+[2026-02-02T08:00:00+0000] [PACMAN] Running 'pacman -Syu'
+[2026-02-02T08:00:10+0000] [ALPM] transaction started
+[2026-02-02T08:00:10+0000] [ALPM] upgraded gelly (4.0.6-10 -> 4.1.0-1)
+[2026-02-02T08:00:10+0000] [ALPM] warning: /etc/gelly.conf installed as /etc/gelly.conf.pacnew
+[2026-02-02T08:00:11+0000] [ALPM] transaction completed
+";
+        parse_fixture(content)
+    }
+
+    fn output(command: impl FnOnce(&mut Vec<u8>) -> Result<(), CommandError>) -> String {
+        let mut out = Vec::new();
+        command(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_list() {
+        assert_eq!(
+            output(|out| list(&logs(), out)),
+            "\
+1769628995\t2026-01-28 19:36:35\tcompleted\t1\tpacman -S gelly
+1770019200\t2026-02-02 08:00:00\tcompleted\t1\tpacman -Syu
+"
+        );
+    }
+
+    #[test]
+    fn test_history() {
+        assert_eq!(
+            output(|out| history(&logs(), "gelly", out)),
+            "\
+2026-01-28 19:36:35\tinstalled\t4.0.6-10
+2026-02-02 08:00:00\tupgraded\t4.0.6-10 -> 4.1.0-1
+"
+        );
+        assert!(matches!(
+            history(&logs(), "puckrat", &mut Vec::new()),
+            Err(CommandError::UnknownPackage(_))
+        ));
+    }
+
+    #[test]
+    fn test_show() {
+        let id = LogKey {
+            timestamp: 1770019200,
+            offset: 0,
+        };
+        assert_eq!(
+            output(|out| show(&logs(), &id, out)),
+            "\
+id\t1770019200
+command\tpacman -Syu
+start\t2026-02-02 08:00:00
+end\t2026-02-02 08:00:11
+status\tcompleted
+upgraded\tgelly\t4.0.6-10 -> 4.1.0-1
+warning\t/etc/gelly.conf installed as /etc/gelly.conf.pacnew
+"
+        );
+        assert!(matches!(
+            show(&logs(), &"1770019201".parse().unwrap(), &mut Vec::new()),
+            Err(CommandError::UnknownTransaction(_))
+        ));
+    }
+
+    #[test]
+    fn test_since() {
+        let date = crate::filter::parse_date("2026-02-01").unwrap();
+        assert_eq!(
+            output(|out| since(&logs(), date, out)),
+            "2026-02-02 08:00:00\tupgraded\tgelly\t4.0.6-10 -> 4.1.0-1\n"
+        );
+    }
+
+    fn installed() -> PackageSet {
+        PackageSet::from([
+            ("gelly".to_string(), "4.1.0-1".to_string()),
+            ("linux".to_string(), "6.14.7.arch2-1".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_state() {
+        let time = crate::filter::parse_date("2026-02-01").unwrap();
+        assert_eq!(
+            output(|out| state(&logs(), time, &installed(), out)),
+            "\
+gelly\t4.0.6-10
+linux\t6.14.7.arch2-1
+"
+        );
+        let time = crate::filter::parse_date("2026-01-01").unwrap();
+        assert_eq!(
+            output(|out| state(&logs(), time, &installed(), out)),
+            "linux\t6.14.7.arch2-1\n"
+        );
+    }
+
+    #[test]
+    fn test_state_diff() {
+        let time = crate::filter::parse_date("2026-02-01").unwrap();
+        assert_eq!(
+            output(|out| state_diff(&logs(), time, &installed(), out)),
+            "upgraded\tgelly\t4.0.6-10 -> 4.1.0-1\n"
+        );
+    }
+
+    #[test]
+    fn test_rollback() {
+        let id = LogKey {
+            timestamp: 1770019200,
+            offset: 0,
+        };
+        let cached = [CachedPackage {
+            path: "/var/cache/pacman/pkg/gelly-4.0.6-10-x86_64.pkg.tar.zst".into(),
+            name: "gelly".to_string(),
+            version: "4.0.6-10".to_string(),
+        }];
+        assert_eq!(
+            output(|out| rollback(&logs(), &id, &cached, out)),
+            "\
+#!/bin/sh
+# Rollback of the transaction started 2026-02-02 08:00:00: pacman -Syu
+# Generated by puckrat. Review before running, nothing has been executed.
+set -e
+pacman -U '/var/cache/pacman/pkg/gelly-4.0.6-10-x86_64.pkg.tar.zst'
+"
+        );
+        assert_eq!(
+            output(|out| rollback(&logs(), &id, &[], out)),
+            "\
+#!/bin/sh
+# Rollback of the transaction started 2026-02-02 08:00:00: pacman -Syu
+# Generated by puckrat. Review before running, nothing has been executed.
+#
+# Not in the package cache, so left out:
+#   gelly 4.0.6-10
+set -e
+"
+        );
+    }
+}
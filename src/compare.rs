@@ -0,0 +1,171 @@
+//! Net change in installed packages between two points in the log
+use std::ops::Bound;
+
+use crate::filter;
+use crate::logparse::{LogDB, LogKey, Transaction};
+use crate::snapshot::{self, PackageDiff};
+
+/// A point to compare at, as given on the command line
+#[derive(Debug, Clone, PartialEq)]
+pub enum Point {
+    /// A transaction by the id printed by `list`
+    Transaction(LogKey),
+    /// A date or date and time, as for `filter::parse_time`
+    Time(i64),
+}
+
+pub fn parse_point(point: &str) -> Result<Point, String> {
+    match point.parse() {
+        Ok(id) => Ok(Point::Transaction(id)),
+        Err(_) => filter::parse_time(point).map(Point::Time),
+    }
+}
+
+/// The moment a transaction starts
+pub fn before(tx: &Transaction) -> LogKey {
+    tx.key.clone()
+}
+
+/// The moment after a transaction, when the next one starts
+pub fn after(db: &LogDB, tx: &Transaction) -> LogKey {
+    db.transactions
+        .range((Bound::Excluded(&tx.key), Bound::Unbounded))
+        .next()
+        .map(|(key, _)| key.clone())
+        .unwrap_or(LogKey {
+            timestamp: i64::MAX,
+            offset: 0,
+        })
+}
+
+/// The moment after everything logged by `timestamp`
+pub fn at(timestamp: i64) -> LogKey {
+    LogKey {
+        timestamp: timestamp.saturating_add(1),
+        offset: 0,
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct DiffCounts {
+    pub added: usize,
+    pub removed: usize,
+    pub upgraded: usize,
+    pub downgraded: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub from: LogKey,
+    pub to: LogKey,
+    pub changes: Vec<PackageDiff>,
+    /// Change in installed size for each change, in bytes. `None` until looked up, and
+    /// `None` for a change whose package versions could not be found.
+    pub size_deltas: Option<Vec<Option<i64>>>,
+}
+
+impl Comparison {
+    /// Compares what was installed at `from` with what was installed at `to`
+    pub fn new(db: &LogDB, from: LogKey, to: LogKey) -> Self {
+        let changes = snapshot::diff(
+            &snapshot::installed_before(db, &from),
+            &snapshot::installed_before(db, &to),
+        );
+        Comparison {
+            from,
+            to,
+            changes,
+            size_deltas: None,
+        }
+    }
+
+    pub fn counts(&self) -> DiffCounts {
+        let mut counts = DiffCounts::default();
+        for change in &self.changes {
+            match change {
+                PackageDiff::Added { .. } => counts.added += 1,
+                PackageDiff::Removed { .. } => counts.removed += 1,
+                PackageDiff::Upgraded { .. } => counts.upgraded += 1,
+                PackageDiff::Downgraded { .. } => counts.downgraded += 1,
+            }
+        }
+        counts
+    }
+
+    /// The total change in installed size and the number of changes it leaves out for
+    /// lack of sizes, once they have been looked up
+    pub fn size_delta(&self) -> Option<(i64, usize)> {
+        let deltas = self.size_deltas.as_ref()?;
+        let total = deltas.iter().flatten().sum();
+        let unknown = deltas.iter().filter(|delta| delta.is_none()).count();
+        Some((total, unknown))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logparse::parse_fixture;
+
+    #[test]
+    fn test_comparison() {
+        let content = "\
+[2026-01-28T19:36:35+0000] [PACMAN] Running 'pacman -S gelly'
+[2026-01-28T19:36:50+0000] [ALPM] transaction started
+[2026-01-28T19:36:50+0000] [ALPM] installed gelly (4.0.6-10)
+[2026-01-28T19:36:51+0000] [ALPM] transaction completed
+[2026-02-02T08:00:00+0000] [PACMAN] Running 'pacman -Syu'
+[2026-02-02T08:00:10+0000] [ALPM] transaction started
+[2026-02-02T08:00:10+0000] [ALPM] upgraded gelly (4.0.6-10 -> 4.1.0-1)
+[2026-02-02T08:00:10+0000] [ALPM] upgraded linux (6.14.6.arch1-1 -> 6.14.7.arch2-1)
+[2026-02-02T08:00:11+0000] [ALPM] transaction completed
+[2026-02-03T08:00:00+0000] [PACMAN] Running 'pacman -Syu'
+[2026-02-03T08:00:10+0000] [ALPM] transaction started
+[2026-02-03T08:00:10+0000] [ALPM] upgraded gelly (4.1.0-1 -> 4.1.1-1)
+[2026-02-03T08:00:10+0000] [ALPM] downgraded linux (6.14.7.arch2-1 -> 6.14.5.arch1-1)
+[2026-02-03T08:00:11+0000] [ALPM] transaction completed
+";
+        let db = parse_fixture(content);
+        let txs: Vec<&Transaction> = db.transactions.values().collect();
+
+        // Both transactions, with gelly's intermediate version folded away
+        let comparison = Comparison::new(&db, before(txs[1]), after(&db, txs[2]));
+        assert_eq!(
+            comparison.changes,
+            vec![
+                PackageDiff::Upgraded {
+                    package: "gelly".to_string(),
+                    old_version: "4.0.6-10".to_string(),
+                    new_version: "4.1.1-1".to_string(),
+                },
+                PackageDiff::Downgraded {
+                    package: "linux".to_string(),
+                    old_version: "6.14.6.arch1-1".to_string(),
+                    new_version: "6.14.5.arch1-1".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            comparison.counts(),
+            DiffCounts {
+                upgraded: 1,
+                downgraded: 1,
+                ..Default::default()
+            }
+        );
+        assert_eq!(comparison.size_delta(), None);
+
+        let comparison = Comparison::new(&db, at(0), before(txs[1]));
+        assert_eq!(comparison.counts().added, 1);
+
+        assert_eq!(
+            parse_point("1769990400.2"),
+            Ok(Point::Transaction(LogKey {
+                timestamp: 1769990400,
+                offset: 2
+            }))
+        );
+        assert_eq!(parse_point("2026-02-02"), Ok(Point::Time(1769990400)));
+        assert!(parse_point("tuesday").is_err());
+    }
+}
@@ -0,0 +1,289 @@
+//! The dependency tree on the package screen, flattened into the rows of a list.
+//! Forward dependencies expand into their own dependencies and reverse dependencies into
+//! the packages requiring them.
+use std::collections::{HashMap, HashSet};
+
+use crate::alpmutil::{Dependencies, Dependency};
+
+/// Answers to dependency lookups by package, `None` for packages alpm does not know
+pub type DependencyCache = HashMap<String, Option<Dependencies>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Section {
+    DependsOn,
+    OptionalDeps,
+    RequiredBy,
+    OptionalFor,
+}
+
+impl Section {
+    pub const ALL: [Section; 4] = [
+        Section::DependsOn,
+        Section::OptionalDeps,
+        Section::RequiredBy,
+        Section::OptionalFor,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            Section::DependsOn => "Depends on",
+            Section::OptionalDeps => "Optional dependencies",
+            Section::RequiredBy => "Required by",
+            Section::OptionalFor => "Optional for",
+        }
+    }
+
+    fn entries(self, dependencies: &Dependencies) -> &[Dependency] {
+        match self {
+            Section::DependsOn => &dependencies.depends_on,
+            Section::OptionalDeps => &dependencies.optional_deps,
+            Section::RequiredBy => &dependencies.required_by,
+            Section::OptionalFor => &dependencies.optional_for,
+        }
+    }
+
+    /// The section a node's children come from
+    fn children(self) -> Section {
+        match self {
+            Section::DependsOn | Section::OptionalDeps => Section::DependsOn,
+            Section::RequiredBy | Section::OptionalFor => Section::RequiredBy,
+        }
+    }
+}
+
+/// Identifies a node by its section and the satisfying packages leading to it
+pub type NodePath = (Section, Vec<String>);
+
+#[derive(Debug, PartialEq)]
+pub enum Row<'a> {
+    Section(Section),
+    Node {
+        depth: usize,
+        dependency: &'a Dependency,
+        path: NodePath,
+        expanded: bool,
+        /// Whether it has a package that is not already further up the tree
+        expandable: bool,
+    },
+    /// Dependencies of a package that are still being looked up
+    Loading {
+        depth: usize,
+        package: String,
+    },
+    /// A section or expanded node without dependencies
+    Empty {
+        depth: usize,
+    },
+}
+
+impl Row<'_> {
+    /// The package a row is about, if any
+    pub fn package(&self) -> Option<&str> {
+        match self {
+            Row::Node { dependency, .. } => dependency.satisfier.as_deref(),
+            _ => None,
+        }
+    }
+}
+
+/// The rows for the tree of `root`, with the nodes in `expanded` expanded
+pub fn rows<'a>(
+    root: &str,
+    cache: &'a DependencyCache,
+    expanded: &HashSet<NodePath>,
+) -> Vec<Row<'a>> {
+    let mut rows = Vec::new();
+    let Some(entry) = cache.get(root) else {
+        rows.push(Row::Loading {
+            depth: 0,
+            package: root.to_string(),
+        });
+        return rows;
+    };
+    let Some(dependencies) = entry else {
+        return rows;
+    };
+    for section in Section::ALL {
+        rows.push(Row::Section(section));
+        let ancestors = vec![root.to_string()];
+        add_children(
+            &mut rows,
+            cache,
+            expanded,
+            section.entries(dependencies),
+            (section, Vec::new()),
+            &ancestors,
+        );
+    }
+    rows
+}
+
+fn add_children<'a>(
+    rows: &mut Vec<Row<'a>>,
+    cache: &'a DependencyCache,
+    expanded: &HashSet<NodePath>,
+    entries: &'a [Dependency],
+    parent: NodePath,
+    ancestors: &[String],
+) {
+    let depth = parent.1.len() + 1;
+    if entries.is_empty() {
+        rows.push(Row::Empty { depth });
+    }
+    for dependency in entries {
+        let mut path = parent.clone();
+        if let Some(package) = &dependency.satisfier {
+            path.1.push(package.clone());
+        }
+        let expandable = dependency
+            .satisfier
+            .as_ref()
+            .is_some_and(|package| !ancestors.contains(package));
+        let is_expanded = expandable && expanded.contains(&path);
+        rows.push(Row::Node {
+            depth,
+            dependency,
+            path: path.clone(),
+            expanded: is_expanded,
+            expandable,
+        });
+        let Some(package) = dependency.satisfier.as_ref().filter(|_| is_expanded) else {
+            continue;
+        };
+        match cache.get(package) {
+            None => rows.push(Row::Loading {
+                depth: depth + 1,
+                package: package.clone(),
+            }),
+            Some(None) => rows.push(Row::Empty { depth: depth + 1 }),
+            Some(Some(dependencies)) => {
+                let mut ancestors = ancestors.to_vec();
+                ancestors.push(package.clone());
+                let section = path.0.children();
+                add_children(
+                    rows,
+                    cache,
+                    expanded,
+                    section.entries(dependencies),
+                    (section, path.1),
+                    &ancestors,
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alpmutil::Resolution;
+
+    fn dependency(spec: &str, satisfier: Option<&str>, resolution: Resolution) -> Dependency {
+        Dependency {
+            spec: spec.to_string(),
+            satisfier: satisfier.map(|satisfier| satisfier.to_string()),
+            resolution,
+        }
+    }
+
+    fn dependencies(depends_on: Vec<Dependency>, required_by: Vec<Dependency>) -> Dependencies {
+        Dependencies {
+            depends_on,
+            optional_deps: Vec::new(),
+            required_by,
+            optional_for: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_rows() {
+        let mut cache = DependencyCache::new();
+        cache.insert(
+            "gelly".to_string(),
+            Some(dependencies(
+                vec![
+                    dependency("sh", Some("bash"), Resolution::Installed),
+                    dependency("libgelly>=4", None, Resolution::Missing),
+                ],
+                vec![dependency("bash", Some("bash"), Resolution::Installed)],
+            )),
+        );
+        cache.insert(
+            "bash".to_string(),
+            Some(dependencies(
+                vec![dependency(
+                    "readline",
+                    Some("readline"),
+                    Resolution::Installed,
+                )],
+                vec![dependency("gelly", Some("gelly"), Resolution::Installed)],
+            )),
+        );
+
+        let bash_path = (Section::DependsOn, vec!["bash".to_string()]);
+        let readline_path = (
+            Section::DependsOn,
+            vec!["bash".to_string(), "readline".to_string()],
+        );
+        let expanded = HashSet::from([bash_path.clone(), readline_path.clone()]);
+        let rows = rows("gelly", &cache, &expanded);
+        let gelly = &cache["gelly"].as_ref().unwrap();
+        let bash = &cache["bash"].as_ref().unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                Row::Section(Section::DependsOn),
+                Row::Node {
+                    depth: 1,
+                    dependency: &gelly.depends_on[0],
+                    path: bash_path,
+                    expanded: true,
+                    expandable: true,
+                },
+                Row::Node {
+                    depth: 2,
+                    dependency: &bash.depends_on[0],
+                    path: readline_path,
+                    expanded: true,
+                    expandable: true,
+                },
+                Row::Loading {
+                    depth: 3,
+                    package: "readline".to_string(),
+                },
+                Row::Node {
+                    depth: 1,
+                    dependency: &gelly.depends_on[1],
+                    path: (Section::DependsOn, Vec::new()),
+                    expanded: false,
+                    expandable: false,
+                },
+                Row::Section(Section::OptionalDeps),
+                Row::Empty { depth: 1 },
+                Row::Section(Section::RequiredBy),
+                Row::Node {
+                    depth: 1,
+                    dependency: &gelly.required_by[0],
+                    path: (Section::RequiredBy, vec!["bash".to_string()]),
+                    expanded: false,
+                    expandable: true,
+                },
+                Row::Section(Section::OptionalFor),
+                Row::Empty { depth: 1 },
+            ]
+        );
+        assert_eq!(rows[1].package(), Some("bash"));
+
+        // gelly is required by bash which is required by gelly, so the cycle stops there
+        let expanded = HashSet::from([(Section::RequiredBy, vec!["bash".to_string()])]);
+        let rows = super::rows("gelly", &cache, &expanded);
+        assert!(matches!(
+            rows[rows.len() - 3],
+            Row::Node {
+                expanded: false,
+                expandable: false,
+                ..
+            }
+        ));
+    }
+}
@@ -1,21 +1,212 @@
+//! Sources of messages for the UI thread: key presses and the tick timer
 use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
-use std::io;
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::Duration;
 
+use log::warn;
+
+use crate::export::ExportFormat;
 use crate::message::Message;
-use crate::model::Model;
+use crate::model::{Model, Screen};
+
+pub const TICK_RATE: Duration = Duration::from_millis(250);
+
+/// Forwards key presses and resizes until the receiving end goes away
+pub fn spawn_input(sender: Sender<Message>) {
+    thread::spawn(move || {
+        loop {
+            match event::read() {
+                Ok(Event::Key(key_event)) if key_event.kind == KeyEventKind::Press => {
+                    if sender.send(Message::Key(key_event)).is_err() {
+                        break;
+                    }
+                }
+                Ok(Event::Resize(..)) => {
+                    if sender.send(Message::Resize).is_err() {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(error) => {
+                    warn!("Failed to read terminal events: {}", error);
+                    break;
+                }
+            }
+        }
+    });
+}
+
+pub fn spawn_ticks(sender: Sender<Message>, rate: Duration) {
+    thread::spawn(move || {
+        while sender.send(Message::Tick).is_ok() {
+            thread::sleep(rate);
+        }
+    });
+}
+
+pub fn handle_key(model: &Model, key: KeyEvent) -> Option<Message> {
+    if model.filter_editing {
+        return handle_filter_key(key);
+    }
+    if model.export_prompt {
+        return handle_export_key(key);
+    }
+    if model.date_prompt.is_some() {
+        return handle_date_key(key);
+    }
+    match key.code {
+        KeyCode::Char('q') => return Some(Message::Quit),
+        KeyCode::Char('?') if !matches!(model.screen(), Screen::Help) => {
+            return Some(Message::ShowHelp);
+        }
+        KeyCode::Esc => return Some(Message::Back),
+        _ => {}
+    }
+    match model.screen() {
+        Screen::Main => handle_main_key(key),
+        Screen::Package { .. } => handle_package_key(key),
+        Screen::History { .. } => handle_history_key(key),
+        Screen::Rollback { .. } => handle_rollback_key(key),
+        Screen::Packages { .. } => handle_packages_key(key),
+        Screen::Diagnostics(_) | Screen::Compare { .. } => handle_list_key(key),
+        Screen::Stats(_) | Screen::Help => None,
+    }
+}
+
+/// Keys and what they do on a screen, for the help screen. Keep in step with the
+/// handlers below.
+pub fn key_help(screen: &Screen) -> &'static [(&'static str, &'static str)] {
+    match screen {
+        Screen::Main => &[
+            ("j / k", "Move down / up"),
+            ("/", "Filter by package, type:, since:, until: or re:"),
+            ("n / N", "Next / previous match of the filter"),
+            ("i, Enter", "Show the selected package"),
+            ("h", "Show the history of the selected package"),
+            ("d", "Show lines that could not be parsed"),
+            ("e", "Export the visible events"),
+            ("r", "Roll back the selected transaction"),
+            ("m", "Mark the selected transaction to compare"),
+            ("M", "Mark a date to compare"),
+            ("s", "Show statistics"),
+            ("p", "Show installed packages by install reason"),
+        ],
+        Screen::Package { .. } => &[
+            ("Tab", "Switch between the details and the dependencies"),
+            (
+                "j / k",
+                "Scroll the details or move down / up the dependencies",
+            ),
+            ("Space", "Expand or collapse a dependency"),
+            ("Enter", "Show the package of a dependency"),
+            ("h", "Show the history of the package"),
+        ],
+        Screen::History { .. } => &[("j / k", "Move down / up"), ("i", "Show the package")],
+        Screen::Packages { .. } => &[
+            ("Tab", "Switch between explicit, dependencies and orphans"),
+            ("j / k", "Move down / up"),
+            ("i, Enter", "Show the selected package"),
+            ("h", "Show the history of the selected package"),
+        ],
+        Screen::Rollback { .. } => &[
+            ("j / k", "Move down / up"),
+            ("w", "Write the rollback script to a file"),
+        ],
+        Screen::Diagnostics(_) | Screen::Compare { .. } => &[("j / k", "Move down / up")],
+        Screen::Stats(_) | Screen::Help => &[],
+    }
+}
+
+/// Keys that work on every screen
+pub const GLOBAL_KEY_HELP: &[(&str, &str)] =
+    &[("?", "Show this help"), ("Esc", "Go back"), ("q", "Quit")];
+
+fn handle_main_key(key: KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Char('d') => Some(Message::ShowDiagnostics),
+        KeyCode::Char('h') => Some(Message::ShowHistory),
+        KeyCode::Char('i') | KeyCode::Enter => Some(Message::ShowPackage),
+        KeyCode::Char('/') => Some(Message::StartFilter),
+        KeyCode::Char('n') => Some(Message::NextMatch),
+        KeyCode::Char('N') => Some(Message::PreviousMatch),
+        KeyCode::Char('e') => Some(Message::StartExport),
+        KeyCode::Char('r') => Some(Message::ShowRollback),
+        KeyCode::Char('m') => Some(Message::MarkCompare),
+        KeyCode::Char('M') => Some(Message::StartDateMark),
+        KeyCode::Char('s') => Some(Message::ShowStats),
+        KeyCode::Char('p') => Some(Message::ShowPackages),
+        _ => handle_list_key(key),
+    }
+}
+
+fn handle_package_key(key: KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Char('h') => Some(Message::ShowHistory),
+        KeyCode::Char(' ') => Some(Message::ToggleDependency),
+        KeyCode::Enter => Some(Message::OpenDependency),
+        KeyCode::Tab => Some(Message::SwitchPane),
+        _ => handle_list_key(key),
+    }
+}
 
-pub fn handle_event(_: &Model) -> io::Result<Option<Message>> {
-    match event::read()? {
-        Event::Key(key_event) if key_event.kind == KeyEventKind::Press => Ok(handle_key(key_event)),
-        _ => Ok(None),
+fn handle_packages_key(key: KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Tab => Some(Message::SwitchView),
+        KeyCode::Char('i') | KeyCode::Enter => Some(Message::ShowPackage),
+        KeyCode::Char('h') => Some(Message::ShowHistory),
+        _ => handle_list_key(key),
     }
 }
 
-fn handle_key(key: KeyEvent) -> Option<Message> {
+fn handle_history_key(key: KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Char('i') => Some(Message::ShowPackage),
+        _ => handle_list_key(key),
+    }
+}
+
+fn handle_rollback_key(key: KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Char('w') => Some(Message::WriteRollbackScript),
+        _ => handle_list_key(key),
+    }
+}
+
+fn handle_list_key(key: KeyEvent) -> Option<Message> {
     match key.code {
         KeyCode::Char('j') => Some(Message::MoveDown),
         KeyCode::Char('k') => Some(Message::MoveUp),
-        KeyCode::Char('q') => Some(Message::Quit),
+        _ => None,
+    }
+}
+
+fn handle_export_key(key: KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Char('j') => Some(Message::Export(ExportFormat::Json)),
+        KeyCode::Char('n') => Some(Message::Export(ExportFormat::Ndjson)),
+        KeyCode::Char('c') => Some(Message::Export(ExportFormat::Csv)),
+        KeyCode::Esc => Some(Message::CancelExport),
+        _ => None,
+    }
+}
+
+fn handle_filter_key(key: KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Char(c) => Some(Message::FilterInput(c)),
+        KeyCode::Backspace => Some(Message::FilterBackspace),
+        KeyCode::Enter => Some(Message::ConfirmFilter),
+        KeyCode::Esc => Some(Message::CancelFilter),
+        _ => None,
+    }
+}
+
+fn handle_date_key(key: KeyEvent) -> Option<Message> {
+    match key.code {
+        KeyCode::Char(c) => Some(Message::DateInput(c)),
+        KeyCode::Backspace => Some(Message::DateBackspace),
+        KeyCode::Enter => Some(Message::ConfirmDate),
+        KeyCode::Esc => Some(Message::CancelDate),
         _ => None,
     }
 }
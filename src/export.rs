@@ -0,0 +1,240 @@
+//! Export of parsed history for spreadsheets, jq pipelines and dashboards
+use std::io::{self, Write};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use time::{OffsetDateTime, format_description::well_known::Rfc3339};
+
+use crate::logparse::{LogDB, LogEvent, LogKey, Transaction};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// An array of transactions, each with its events
+    Json,
+    /// One event per line, with the key of its transaction
+    Ndjson,
+    /// One event per row, with the key of its transaction
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Json => "json",
+            ExportFormat::Ndjson => "ndjson",
+            ExportFormat::Csv => "csv",
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+
+    #[error("Failed to write JSON: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Failed to write CSV: {0}")]
+    Csv(#[from] csv::Error),
+}
+
+#[derive(Serialize)]
+struct EventRecord<'a> {
+    #[serde(flatten)]
+    key: &'a LogKey,
+    /// The timestamp as RFC 3339 in UTC, for tools that do not read unix time
+    time: String,
+    /// Key of the transaction the event belongs to
+    transaction: &'a LogKey,
+    #[serde(flatten)]
+    event: &'a LogEvent,
+}
+
+#[derive(Serialize)]
+struct TransactionRecord<'a> {
+    #[serde(flatten)]
+    transaction: &'a Transaction,
+    events: Vec<EventRecord<'a>>,
+}
+
+const CSV_HEADER: [&str; 14] = [
+    "timestamp",
+    "offset",
+    "time",
+    "transaction_timestamp",
+    "transaction_offset",
+    "kind",
+    "package",
+    "version",
+    "old_version",
+    "new_version",
+    "command",
+    "name",
+    "output",
+    "message",
+];
+
+/// Writes the events with the given keys, which must be in log order. Transactions are
+/// limited to the events that were asked for.
+pub fn export(
+    db: &LogDB,
+    keys: &[LogKey],
+    format: ExportFormat,
+    mut out: impl Write,
+) -> Result<(), ExportError> {
+    let records = keys.iter().filter_map(|key| {
+        let (key, event) = db.events.get_key_value(key)?;
+        let transaction = db.transaction_for(key)?;
+        Some((
+            transaction,
+            EventRecord {
+                key,
+                time: iso_time(key.timestamp),
+                transaction: &transaction.key,
+                event,
+            },
+        ))
+    });
+
+    match format {
+        ExportFormat::Json => {
+            let mut transactions: Vec<TransactionRecord> = Vec::new();
+            for (transaction, record) in records {
+                match transactions.last_mut() {
+                    Some(last) if last.transaction.key == transaction.key => {
+                        last.events.push(record)
+                    }
+                    _ => transactions.push(TransactionRecord {
+                        transaction,
+                        events: vec![record],
+                    }),
+                }
+            }
+            serde_json::to_writer_pretty(&mut out, &transactions)?;
+            writeln!(out)?;
+        }
+        ExportFormat::Ndjson => {
+            for (_, record) in records {
+                serde_json::to_writer(&mut out, &record)?;
+                writeln!(out)?;
+            }
+        }
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(&mut out);
+            writer.write_record(CSV_HEADER)?;
+            for (_, record) in records {
+                writer.write_record(csv_record(&record))?;
+            }
+            writer.flush()?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn csv_record(record: &EventRecord) -> [String; 14] {
+    let mut row: [String; 14] = Default::default();
+    row[0] = record.key.timestamp.to_string();
+    row[1] = record.key.offset.to_string();
+    row[2].clone_from(&record.time);
+    row[3] = record.transaction.timestamp.to_string();
+    row[4] = record.transaction.offset.to_string();
+    row[5] = record.event.kind().to_string();
+    match record.event {
+        LogEvent::Installed { package, version }
+        | LogEvent::Reinstalled { package, version }
+        | LogEvent::Removed { package, version } => {
+            row[6].clone_from(package);
+            row[7].clone_from(version);
+        }
+        LogEvent::Upgraded {
+            package,
+            old_version,
+            new_version,
+        }
+        | LogEvent::Downgraded {
+            package,
+            old_version,
+            new_version,
+        } => {
+            row[6].clone_from(package);
+            row[8].clone_from(old_version);
+            row[9].clone_from(new_version);
+        }
+        LogEvent::Command { command } => row[10].clone_from(command),
+        LogEvent::Hook { name } => row[11].clone_from(name),
+        LogEvent::Scriptlet { output } => row[12].clone_from(output),
+        LogEvent::Warning { message } | LogEvent::Error { message } => row[13].clone_from(message),
+        LogEvent::SyncDatabases | LogEvent::FullSystemUpgrade => {}
+    }
+    row
+}
+
+fn iso_time(timestamp: i64) -> String {
+    OffsetDateTime::from_unix_timestamp(timestamp)
+        .ok()
+        .and_then(|time| time.format(&Rfc3339).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logparse::parse_fixture;
+
+    fn logs() -> LogDB {
+        let content = "\
+[2026-01-28T19:36:35+0000] [PACMAN] Running 'pacman -S gelly'
+[2026-01-28T19:36:50+0000] [ALPM] transaction started
+[2026-01-28T19:36:50+0000] [ALPM] installed gelly (4.1.0-1)
+[2026-01-28T19:36:51+0000] [ALPM] transaction completed
+[2026-01-28T19:36:52+0000] [ALPM] warning: a \"quoted\", message
+";
+        parse_fixture(content)
+    }
+
+    fn exported(format: ExportFormat) -> String {
+        let db = logs();
+        let keys: Vec<LogKey> = db.events.keys().cloned().collect();
+        let mut out = Vec::new();
+        export(&db, &keys, format, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_export_ndjson() {
+        assert_eq!(
+            exported(ExportFormat::Ndjson),
+            r#"{"timestamp":1769628995,"offset":0,"time":"2026-01-28T19:36:35Z","transaction":{"timestamp":1769628995,"offset":0},"kind":"command","command":"pacman -S gelly"}
+{"timestamp":1769628995,"offset":1,"time":"2026-01-28T19:36:35Z","transaction":{"timestamp":1769628995,"offset":0},"kind":"installed","package":"gelly","version":"4.1.0-1"}
+{"timestamp":1769628995,"offset":2,"time":"2026-01-28T19:36:35Z","transaction":{"timestamp":1769628995,"offset":0},"kind":"warning","message":"a \"quoted\", message"}
+"#
+        );
+    }
+
+    #[test]
+    fn test_export_csv() {
+        assert_eq!(
+            exported(ExportFormat::Csv),
+            "\
+timestamp,offset,time,transaction_timestamp,transaction_offset,kind,package,version,old_version,new_version,command,name,output,message
+1769628995,0,2026-01-28T19:36:35Z,1769628995,0,command,,,,,pacman -S gelly,,,
+1769628995,1,2026-01-28T19:36:35Z,1769628995,0,installed,gelly,4.1.0-1,,,,,,
+1769628995,2,2026-01-28T19:36:35Z,1769628995,0,warning,,,,,,,,\"a \"\"quoted\"\", message\"
+"
+        );
+    }
+
+    #[test]
+    fn test_export_json() {
+        let json: serde_json::Value = serde_json::from_str(&exported(ExportFormat::Json)).unwrap();
+        let transactions = json.as_array().unwrap();
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0]["command"], "pacman -S gelly");
+        assert_eq!(transactions[0]["status"], "completed");
+        assert_eq!(transactions[0]["warnings"][0], "a \"quoted\", message");
+        assert_eq!(transactions[0]["events"].as_array().unwrap().len(), 3);
+        assert_eq!(transactions[0]["events"][1]["package"], "gelly");
+    }
+}
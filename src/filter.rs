@@ -0,0 +1,180 @@
+//! Filtering of the transaction list. A query is a list of whitespace separated terms,
+//! all of which must match:
+//!
+//! - `type:upgraded,downgraded` only shows events of the given kinds
+//! - `since:2026-01-01` and `until:2026-01-31` limit the date range, inclusive
+//! - `re:^lib32-` matches package names against a regular expression
+//! - anything else matches package names containing it
+use std::ops::Range;
+
+use regex::Regex;
+use time::{Date, Duration, PrimitiveDateTime, macros::format_description};
+
+use crate::logparse::{LogEvent, LogKey};
+
+#[derive(Debug)]
+enum PackageMatcher {
+    Substring(String),
+    Regex(Regex),
+}
+
+impl PackageMatcher {
+    fn ranges(&self, package: &str) -> Vec<Range<usize>> {
+        match self {
+            PackageMatcher::Substring(needle) => package
+                .match_indices(needle.as_str())
+                .map(|(start, m)| start..start + m.len())
+                .collect(),
+            PackageMatcher::Regex(regex) => regex
+                .find_iter(package)
+                .filter(|m| !m.is_empty())
+                .map(|m| m.range())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Filter {
+    /// The query as typed
+    pub query: String,
+    /// Why the query could not be parsed, if it couldn't
+    pub error: Option<String>,
+    packages: Vec<PackageMatcher>,
+    kinds: Vec<String>,
+    since: Option<i64>,
+    until: Option<i64>,
+}
+
+impl Filter {
+    pub fn new(query: &str) -> Self {
+        let mut filter = Filter {
+            query: query.to_string(),
+            ..Default::default()
+        };
+        for term in query.split_whitespace() {
+            if let Err(error) = filter.add_term(term) {
+                filter.error = Some(error);
+            }
+        }
+        filter
+    }
+
+    fn add_term(&mut self, term: &str) -> Result<(), String> {
+        if let Some(kinds) = term.strip_prefix("type:") {
+            self.kinds
+                .extend(kinds.split(',').map(|kind| kind.to_lowercase()));
+        } else if let Some(date) = term.strip_prefix("since:") {
+            self.since = Some(parse_date(date)?);
+        } else if let Some(date) = term.strip_prefix("until:") {
+            // Inclusive, so up to the end of that day
+            self.until = Some(parse_date(date)? + Duration::DAY.whole_seconds() - 1);
+        } else if let Some(pattern) = term.strip_prefix("re:") {
+            let regex = Regex::new(pattern).map_err(|e| e.to_string())?;
+            self.packages.push(PackageMatcher::Regex(regex));
+        } else {
+            self.packages
+                .push(PackageMatcher::Substring(term.to_string()));
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, key: &LogKey, event: &LogEvent) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.iter().any(|kind| kind == event.kind()) {
+            return false;
+        }
+        if self.since.is_some_and(|since| key.timestamp < since)
+            || self.until.is_some_and(|until| key.timestamp > until)
+        {
+            return false;
+        }
+        if self.packages.is_empty() {
+            return true;
+        }
+        event.package().is_some_and(|package| {
+            self.packages
+                .iter()
+                .all(|matcher| !matcher.ranges(package).is_empty())
+        })
+    }
+
+    /// Byte ranges of a package name matched by the query, for highlighting
+    pub fn highlights(&self, package: &str) -> Vec<Range<usize>> {
+        let mut ranges: Vec<Range<usize>> = self
+            .packages
+            .iter()
+            .flat_map(|matcher| matcher.ranges(package))
+            .collect();
+        ranges.sort_by_key(|range| range.start);
+        ranges
+    }
+}
+
+// Dates are taken to be UTC, as are the timestamps shown in the list
+pub fn parse_date(date: &str) -> Result<i64, String> {
+    let format = format_description!("[year]-[month]-[day]");
+    Date::parse(date, &format)
+        .map(|date| date.midnight().assume_utc().unix_timestamp())
+        .map_err(|e| format!("invalid date {}: {}", date, e))
+}
+
+/// A date as for `parse_date`, or a date and time as `YYYY-MM-DD HH:MM[:SS]`
+pub fn parse_time(time: &str) -> Result<i64, String> {
+    if !time.contains(' ') {
+        return parse_date(time);
+    }
+    let format = format_description!("[year]-[month]-[day] [hour]:[minute][optional [:[second]]]");
+    PrimitiveDateTime::parse(time, &format)
+        .map(|time| time.assume_utc().unix_timestamp())
+        .map_err(|e| format!("invalid time {}: {}", time, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn upgraded(package: &str) -> LogEvent {
+        LogEvent::Upgraded {
+            package: package.to_string(),
+            old_version: "1.0-1".to_string(),
+            new_version: "1.1-1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_matches() {
+        let key = LogKey {
+            timestamp: 1769657810, // 2026-01-29
+            offset: 0,
+        };
+        let filter = Filter::new("lib type:upgraded,removed since:2026-01-29");
+        assert_eq!(filter.error, None);
+        assert!(filter.matches(&key, &upgraded("lib32-glibc")));
+        assert!(!filter.matches(&key, &upgraded("gcc")));
+        assert!(!filter.matches(
+            &key,
+            &LogEvent::Installed {
+                package: "lib32-glibc".to_string(),
+                version: "1.0-1".to_string(),
+            }
+        ));
+        assert!(!Filter::new("until:2026-01-28").matches(&key, &upgraded("glibc")));
+        assert!(Filter::new("until:2026-01-29").matches(&key, &upgraded("glibc")));
+        assert!(!Filter::new("glibc").matches(&key, &LogEvent::SyncDatabases));
+        assert!(Filter::new("type:sync").matches(&key, &LogEvent::SyncDatabases));
+    }
+
+    #[test]
+    fn test_regex() {
+        let filter = Filter::new("re:^lib.*c$");
+        assert_eq!(filter.highlights("lib32-glibc"), vec![0..11]);
+        assert!(filter.highlights("glibc").is_empty());
+        assert!(Filter::new("re:(").error.is_some());
+    }
+
+    #[test]
+    fn test_highlights() {
+        let filter = Filter::new("ib");
+        assert_eq!(filter.highlights("lib32-glibc"), vec![1..3, 8..10]);
+    }
+}
@@ -0,0 +1,123 @@
+//! Following the live log while pacman appends to it, like `tail -f`
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+
+use log::debug;
+
+use crate::logparse::{LogDB, ParseError, ParseMode};
+
+#[derive(Debug, thiserror::Error)]
+pub enum FollowError {
+    #[error("Failed to read log: {0}")]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct LogFollower {
+    path: PathBuf,
+    /// Bytes of the file parsed so far
+    offset: u64,
+    /// Changes when logrotate moves the log away and pacman creates a new one
+    inode: Option<u64>,
+    mode: ParseMode,
+}
+
+impl LogFollower {
+    /// Follows the log at `path`, of which the first `offset` bytes have already been
+    /// parsed
+    pub fn new(path: PathBuf, offset: u64, mode: ParseMode) -> Self {
+        let inode = std::fs::metadata(&path).ok().map(|metadata| metadata.ino());
+        LogFollower {
+            path,
+            offset,
+            inode,
+            mode,
+        }
+    }
+
+    /// Parses whatever was appended since the last poll into `db`. Returns whether
+    /// anything new was parsed.
+    pub fn poll(&mut self, db: &mut LogDB) -> Result<bool, FollowError> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            // Rotated away and not recreated yet
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(error) => return Err(error.into()),
+        };
+        let metadata = file.metadata()?;
+        if self.inode != Some(metadata.ino()) || metadata.len() < self.offset {
+            debug!("{} was rotated or truncated", self.path.display());
+            db.start_source(&self.path);
+            self.inode = Some(metadata.ino());
+            self.offset = 0;
+        }
+        if metadata.len() == self.offset {
+            return Ok(false);
+        }
+
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        let consumed = db.append(&content, self.mode)?;
+        self.offset += consumed as u64;
+        Ok(consumed > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logparse::TransactionStatus;
+    use crate::testutil::temp_dir;
+
+    #[test]
+    fn test_poll() {
+        let dir = temp_dir("follow");
+        let path = dir.join("pacman.log");
+        let started = "\
+[2026-01-28T19:36:35-0800] [PACMAN] Running 'pacman -S gelly'
+[2026-01-28T19:36:50-0800] [ALPM] transaction started
+";
+        std::fs::write(&path, started).unwrap();
+        let mut db = LogDB::default();
+        db.start_source(&path);
+        db.append(started.as_bytes(), ParseMode::Strict).unwrap();
+        let mut follower = LogFollower::new(path.clone(), started.len() as u64, ParseMode::Strict);
+        assert!(!follower.poll(&mut db).unwrap());
+
+        let append = |text: &str| {
+            let mut file = std::fs::OpenOptions::new()
+                .append(true)
+                .open(&path)
+                .unwrap();
+            std::io::Write::write_all(&mut file, text.as_bytes()).unwrap();
+        };
+        append("[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)\n[2026-01");
+        assert!(follower.poll(&mut db).unwrap());
+        assert_eq!(db.events.len(), 2);
+        let tx = db.transactions.values().next().unwrap();
+        assert_eq!(tx.status, TransactionStatus::InProgress);
+
+        append("-28T19:36:51-0800] [ALPM] transaction completed\n");
+        assert!(follower.poll(&mut db).unwrap());
+        let tx = db.transactions.values().next().unwrap();
+        assert_eq!(tx.status, TransactionStatus::Completed);
+
+        // Truncation starts over from the beginning of the file
+        std::fs::write(
+            &path,
+            "[2026-01-29T10:00:00-0800] [PACMAN] Running 'pacman -Syu'\n",
+        )
+        .unwrap();
+        assert!(follower.poll(&mut db).unwrap());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(db.transactions.len(), 2);
+        assert_eq!(db.sources.len(), 2);
+    }
+}
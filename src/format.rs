@@ -0,0 +1,25 @@
+//! Formatting of values shared by the interface and the subcommands
+use time::macros::format_description;
+
+pub fn render_timestamp(timestamp: i64) -> String {
+    let format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
+    let date = time::OffsetDateTime::from_unix_timestamp(timestamp).unwrap();
+    date.format(&format).unwrap()
+}
+
+/// A size in bytes in binary units, as pacman shows them
+pub fn render_size(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut size = bytes.unsigned_abs() as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    let sign = if bytes < 0 { "-" } else { "" };
+    if unit == 0 {
+        format!("{}{} {}", sign, size, UNITS[unit])
+    } else {
+        format!("{}{:.2} {}", sign, size, UNITS[unit])
+    }
+}
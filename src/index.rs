@@ -0,0 +1,373 @@
+//! The parsed history, kept under $XDG_CACHE_HOME/puckrat between runs. pacman only
+//! appends to its log, so as long as the files are the ones that were indexed only the
+//! lines added to the live log since need parsing. A rotated or truncated log is parsed
+//! again from scratch.
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use bincode::{Decode, Encode};
+use log::{debug, warn};
+
+use crate::logparse::{LogDB, ParseError, ParseMode};
+use crate::logsource;
+
+/// Bumped whenever the layout of the index or of LogDB changes
+const VERSION: u32 = 3;
+
+const EXTENSION: &str = "bin";
+
+/// Guards against allocating whatever a corrupt length asks for
+const DECODE_LIMIT: usize = 1 << 30;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadError {
+    #[error("Failed to read log: {0}")]
+    Io(#[from] io::Error),
+
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+}
+
+/// Tells whether a file is still the one that was indexed
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+struct FileStamp {
+    path: PathBuf,
+    inode: u64,
+    /// Bytes on disk, or for the live log the bytes parsed
+    size: u64,
+}
+
+impl FileStamp {
+    fn read(path: &Path) -> io::Result<Self> {
+        let metadata = fs::metadata(path)?;
+        Ok(FileStamp {
+            path: path.to_path_buf(),
+            inode: metadata.ino(),
+            size: metadata.len(),
+        })
+    }
+}
+
+#[derive(Debug, Encode, Decode)]
+struct Index<D> {
+    version: u32,
+    mode: ParseMode,
+    /// The log and its rotated siblings, oldest first
+    files: Vec<FileStamp>,
+    db: D,
+}
+
+/// Where the index for the log at `path` is kept. Each log gets its own, as a sysroot
+/// or `--log` may point elsewhere. The name only depends on the absolute path of the log,
+/// so that it stays the same across runs and builds.
+pub fn index_path(path: &Path) -> Option<PathBuf> {
+    let cache = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    let path = std::path::absolute(path).ok()?;
+    Some(cache.join("puckrat").join(index_name(&path)))
+}
+
+fn index_name(path: &Path) -> String {
+    format!(
+        "index-v{}-{:016x}.{}",
+        VERSION,
+        fnv1a(path.as_os_str().as_bytes()),
+        EXTENSION
+    )
+}
+
+// 64-bit FNV-1a, which unlike std's hashers is fixed
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Whether a file in the cache directory is an index in a format no longer read, which
+/// would otherwise stay behind forever
+fn is_stale(name: &str) -> bool {
+    let current = format!("index-v{}-", VERSION);
+    name.starts_with("index-")
+        && !(name.starts_with(&current) && name.ends_with(&format!(".{}", EXTENSION)))
+}
+
+// Indexes of other logs in the current format are left alone
+fn prune(dir: &Path) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_str().is_some_and(is_stale) {
+            debug!("Removing stale index {}", entry.path().display());
+            if let Err(error) = fs::remove_file(entry.path()) {
+                warn!("Failed to remove {}: {}", entry.path().display(), error);
+            }
+        }
+    }
+}
+
+/// Reads the history of the log at `path`, from the index at `index` where it is still
+/// valid, and updates the index. Returns the history and the number of bytes of the live
+/// log parsed, which is where following it picks up.
+pub fn load(path: &Path, index: Option<&Path>, mode: ParseMode) -> Result<(LogDB, u64), LoadError> {
+    // Indexed paths are absolute, as the same log may be given relative to another
+    // directory next time
+    let path = std::path::absolute(path)?;
+    let path = path.as_path();
+    let files = logsource::discover(path)?
+        .iter()
+        .map(|path| FileStamp::read(path))
+        .collect::<io::Result<Vec<_>>>()?;
+
+    if let Some(index) = index
+        && let Some(indexed) = read_index(index)
+        && indexed.version == VERSION
+        && indexed.mode == mode
+        && let Some(offset) = resume_offset(&indexed.files, &files, path)
+    {
+        debug!("Resuming {} from {}", path.display(), index.display());
+        let mut db = indexed.db;
+        let mut files = indexed.files;
+        let parsed = parse_tail(path, offset, &mut db, mode)?;
+        if let Some(live) = files.last_mut().filter(|file| file.path == path)
+            && parsed > 0
+        {
+            live.size += parsed;
+            write_index(index, &files, &db, mode);
+        }
+        return Ok((db, offset + parsed));
+    }
+
+    let history = logsource::read_history(path)?;
+    let (rotated, live) = match history.files.split_last() {
+        Some((last, rotated)) if last.path == path => (rotated, Some(last)),
+        _ => (history.files.as_slice(), None),
+    };
+    let mut db = LogDB::from_files(rotated, mode)?;
+    let mut offset = 0;
+    if let Some(live) = live {
+        // pacman may be halfway through writing a line, which is left to following. The
+        // rest is counted back from the end, as decoding may have changed the length of
+        // what comes before it.
+        db.start_source(&live.path);
+        let parsed = db.append(live.content.as_bytes(), mode)?;
+        offset = live.size - (live.content.len() - parsed) as u64;
+    }
+    for (skipped, error) in &history.unreadable {
+        db.skipped(skipped, error);
+    }
+    let mut files = Vec::new();
+    for log in &history.files {
+        let mut file = FileStamp::read(&log.path)?;
+        // The live log may have grown since it was read, and its last line may not have
+        // been parsed
+        if log.path == path {
+            file.size = offset;
+        }
+        files.push(file);
+    }
+    // Skipped files are tried again next time rather than left out of the index
+    if let Some(index) = index
+        && history.unreadable.is_empty()
+    {
+        write_index(index, &files, &db, mode);
+    }
+    Ok((db, offset))
+}
+
+/// Where to carry on parsing the live log, if the files are still those that were
+/// indexed and the live log has at most grown
+fn resume_offset(indexed: &[FileStamp], current: &[FileStamp], live: &Path) -> Option<u64> {
+    let (last, rotated) = indexed.split_last()?;
+    if current.len() != indexed.len() || current[..rotated.len()] != *rotated {
+        return None;
+    }
+    let now = current.last()?;
+    let grown = last.path == live
+        && now.path == last.path
+        && now.inode == last.inode
+        && now.size >= last.size;
+    (last == now || grown).then_some(last.size)
+}
+
+/// Parses the live log from `offset` on and returns the number of bytes parsed
+fn parse_tail(path: &Path, offset: u64, db: &mut LogDB, mode: ParseMode) -> Result<u64, LoadError> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(error) => return Err(error.into()),
+    };
+    file.seek(SeekFrom::Start(offset))?;
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    Ok(db.append(&content, mode)? as u64)
+}
+
+// A missing or unreadable index only means parsing everything
+fn read_index(index: &Path) -> Option<Index<LogDB>> {
+    let start = std::time::Instant::now();
+    let bytes = fs::read(index).ok()?;
+    let config = bincode::config::standard().with_limit::<DECODE_LIMIT>();
+    let (indexed, _): (Index<LogDB>, _) = bincode::decode_from_slice(&bytes, config)
+        .inspect_err(|error| warn!("Ignoring the index at {}: {}", index.display(), error))
+        .ok()?;
+    debug!(
+        "Read {} events from the index in {:?}",
+        indexed.db.events.len(),
+        start.elapsed()
+    );
+    Some(indexed)
+}
+
+// Written to a temporary file first, so that a reader never sees half an index
+fn write_index(index: &Path, files: &[FileStamp], db: &LogDB, mode: ParseMode) {
+    let write = || -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = index.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let temporary = index.with_extension("tmp");
+        let mut out = BufWriter::new(File::create(&temporary)?);
+        let contents = Index {
+            version: VERSION,
+            mode,
+            files: files.to_vec(),
+            db,
+        };
+        bincode::encode_into_std_write(&contents, &mut out, bincode::config::standard())?;
+        io::Write::flush(&mut out)?;
+        fs::rename(&temporary, index)?;
+        if let Some(dir) = index.parent() {
+            prune(dir);
+        }
+        Ok(())
+    };
+    if let Err(error) = write() {
+        warn!(
+            "Failed to write the index to {}: {}",
+            index.display(),
+            error
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logparse::parse_fixture;
+    use crate::testutil::temp_dir;
+
+    fn stamp(path: &str, inode: u64, size: u64) -> FileStamp {
+        FileStamp {
+            path: PathBuf::from(path),
+            inode,
+            size,
+        }
+    }
+
+    #[test]
+    fn test_resume_offset() {
+        let live = Path::new("pacman.log");
+        let indexed = [stamp("pacman.log.1.gz", 1, 100), stamp("pacman.log", 2, 50)];
+        let grown = [stamp("pacman.log.1.gz", 1, 100), stamp("pacman.log", 2, 80)];
+        assert_eq!(resume_offset(&indexed, &indexed, live), Some(50));
+        assert_eq!(resume_offset(&indexed, &grown, live), Some(50));
+
+        let truncated = [stamp("pacman.log.1.gz", 1, 100), stamp("pacman.log", 2, 20)];
+        assert_eq!(resume_offset(&indexed, &truncated, live), None);
+        let rotated = [
+            stamp("pacman.log.2.gz", 1, 100),
+            stamp("pacman.log.1", 2, 80),
+            stamp("pacman.log", 3, 0),
+        ];
+        assert_eq!(resume_offset(&indexed, &rotated, live), None);
+        let recreated = [stamp("pacman.log.1.gz", 1, 100), stamp("pacman.log", 3, 80)];
+        assert_eq!(resume_offset(&indexed, &recreated, live), None);
+    }
+
+    #[test]
+    fn test_index_name() {
+        let name = index_name(Path::new("/var/log/pacman.log"));
+        assert_eq!(name, format!("index-v{}-8faf608120866707.bin", VERSION));
+        assert!(!is_stale(&name));
+        assert!(is_stale("index-0123456789abcdef.json"));
+        assert!(is_stale("index-v1-8faf608120866707.json"));
+        assert!(!is_stale("other.json"));
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = temp_dir("index");
+        let path = dir.join("pacman.log");
+        let index = dir.join("cache").join("index.bin");
+        let first = "\
+[2026-01-28T19:36:35-0800] [PACMAN] Running 'pacman -S gelly'
+[2026-01-28T19:36:50-0800] [ALPM] transaction started
+[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)
+";
+        let second = "[2026-01-28T19:36:51-0800] [ALPM] transaction completed\n";
+        let parsed = |content: &str| {
+            let mut db = parse_fixture(content);
+            db.sources[0].path = path.clone();
+            db
+        };
+
+        fs::write(&path, first).unwrap();
+        let (db, offset) = load(&path, Some(&index), ParseMode::Strict).unwrap();
+        assert!(index.exists());
+        assert_eq!(db, parsed(first));
+        assert_eq!(offset, first.len() as u64);
+
+        // Only the appended line is parsed, carrying on with the open transaction
+        fs::write(&path, format!("{}{}", first, second)).unwrap();
+        let (db, offset) = load(&path, Some(&index), ParseMode::Strict).unwrap();
+        assert_eq!(db, parsed(&format!("{}{}", first, second)));
+        assert_eq!(offset, (first.len() + second.len()) as u64);
+
+        // A truncated log is parsed from scratch
+        fs::write(&path, second).unwrap();
+        let (db, _) = load(&path, Some(&index), ParseMode::Strict).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(db, parsed(second));
+    }
+
+    #[test]
+    fn test_load_partial_line() {
+        let dir = temp_dir("index-partial");
+        let path = dir.join("pacman.log");
+        let complete = "[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)\n";
+        let partial = "[2026-01-28T19:36:51-0800] [ALPM] installed puck";
+        fs::write(&path, format!("{}{}", complete, partial)).unwrap();
+        let (db, offset) = load(&path, None, ParseMode::Strict).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Following picks up at the start of the line still being written
+        assert_eq!(db.events.len(), 1);
+        assert!(db.diagnostics.is_empty());
+        assert_eq!(offset, complete.len() as u64);
+    }
+
+    #[test]
+    fn test_load_resumes_partial_line() {
+        let dir = temp_dir("index-resume");
+        let path = dir.join("pacman.log");
+        let index = dir.join("index.bin");
+        let complete = "[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)\n";
+        let partial = "[2026-01-28T19:36:51-0800] [ALPM] installed puck";
+        fs::write(&path, format!("{}{}", complete, partial)).unwrap();
+        load(&path, Some(&index), ParseMode::Strict).unwrap();
+
+        // The line finished since is parsed whole from the index
+        let finished = format!("{}{}rat (0.1.0-1)\n", complete, partial);
+        fs::write(&path, &finished).unwrap();
+        let (db, offset) = load(&path, Some(&index), ParseMode::Strict).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(db.events.len(), 2);
+        assert!(db.diagnostics.is_empty());
+        assert_eq!(offset, finished.len() as u64);
+    }
+}
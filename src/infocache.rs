@@ -0,0 +1,121 @@
+//! Answers to package lookups, kept so that moving through the list does not ask alpm
+//! about the same packages over and over.
+//!
+//! Answers are keyed by package name alone. A lookup only ever has one answer: from the
+//! local database if the package is installed, otherwise from the first sync database
+//! that has it, otherwise from the package cache. The sync entry of an installed package
+//! is never looked up, so there is nothing to keep apart from its local one. The source
+//! each answer came from is kept with it and decides which database changes make it
+//! stale.
+use std::collections::{HashMap, HashSet};
+
+use crate::alpmutil::{DbChange, PackageInfo, PackageSource};
+
+#[derive(Debug, Default)]
+pub struct PackageInfoCache {
+    /// Answers by package name, `None` for packages alpm does not know
+    entries: HashMap<String, Option<PackageInfo>>,
+    /// Packages asked about, whether answered yet or not
+    requested: HashSet<String>,
+}
+
+impl PackageInfoCache {
+    /// The answer for a package, `None` while it has not arrived
+    pub fn get(&self, package: &str) -> Option<Option<&PackageInfo>> {
+        self.entries.get(package).map(Option::as_ref)
+    }
+
+    /// Notes that a package is being asked about, returning false if it already was
+    pub fn request(&mut self, package: &str) -> bool {
+        if self.requested.contains(package) {
+            return false;
+        }
+        self.requested.insert(package.to_string());
+        true
+    }
+
+    /// Keeps an answer, dropping those to requests made before the last invalidation
+    pub fn insert(&mut self, package: String, info: Option<PackageInfo>) {
+        if self.requested.contains(&package) {
+            self.entries.insert(package, info);
+        }
+    }
+
+    /// Forgets the answers a change to the databases may have made stale
+    pub fn invalidate(&mut self, change: DbChange) {
+        match change {
+            // Installing or removing moves packages between the databases
+            DbChange::Local => self.entries.clear(),
+            // Installed packages are always looked up in the local database
+            DbChange::Sync => self.entries.retain(|_, info| {
+                info.as_ref()
+                    .is_some_and(|info| info.source == PackageSource::Local)
+            }),
+        }
+        self.requested
+            .retain(|package| self.entries.contains_key(package));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpm::{PackageReason, PackageValidation};
+
+    fn info(source: PackageSource) -> PackageInfo {
+        PackageInfo {
+            source,
+            version: "4.1.0-1".to_string(),
+            description: "Gelly".to_string(),
+            arch: None,
+            url: None,
+            licenses: Vec::new(),
+            groups: Vec::new(),
+            provides: Vec::new(),
+            conflics_with: Vec::new(),
+            replaces: Vec::new(),
+            installed_size: 0,
+            packager: None,
+            build_date: 0,
+            install_date: None,
+            install_reason: PackageReason::Explicit,
+            install_script: false,
+            validated_by: PackageValidation::NONE,
+        }
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let mut cache = PackageInfoCache::default();
+        for package in ["gelly", "linux", "gone", "pending"] {
+            assert!(cache.request(package));
+        }
+        assert!(!cache.request("gelly"));
+        cache.insert("gelly".to_string(), Some(info(PackageSource::Local)));
+        cache.insert(
+            "linux".to_string(),
+            Some(info(PackageSource::Sync("core".to_string()))),
+        );
+        cache.insert("gone".to_string(), None);
+        cache.insert("unrequested".to_string(), None);
+        assert!(cache.get("gelly").is_some_and(|info| info.is_some()));
+        assert_eq!(cache.get("gone"), Some(None));
+        assert_eq!(cache.get("pending"), None);
+        assert_eq!(cache.get("unrequested"), None);
+
+        cache.invalidate(DbChange::Sync);
+        assert!(cache.get("gelly").is_some());
+        assert_eq!(cache.get("linux"), None);
+        assert_eq!(cache.get("gone"), None);
+        assert!(!cache.request("gelly"));
+        assert!(cache.request("linux"));
+
+        // A late answer from before the change is not kept
+        cache.insert("pending".to_string(), None);
+        assert_eq!(cache.get("pending"), None);
+
+        cache.invalidate(DbChange::Local);
+        assert_eq!(cache.get("gelly"), None);
+        assert!(cache.request("gelly"));
+    }
+}
@@ -0,0 +1,133 @@
+//! Installed packages grouped by why they were installed, to find cruft such as orphaned
+//! dependencies
+use std::collections::HashMap;
+
+use crate::logparse::{LogDB, LogEvent, LogKey};
+
+/// A package in the local database
+#[derive(Debug, Clone)]
+pub struct InstalledPackage {
+    pub name: String,
+    pub version: String,
+    /// Installed explicitly rather than as a dependency
+    pub explicit: bool,
+    /// Whether any installed package depends on it, optionally or not
+    pub required: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PackageView {
+    #[default]
+    Explicit,
+    Dependencies,
+    /// Dependencies nothing requires any more, like `pacman -Qdtq`
+    Orphans,
+}
+
+impl PackageView {
+    pub const ALL: [PackageView; 3] = [
+        PackageView::Explicit,
+        PackageView::Dependencies,
+        PackageView::Orphans,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            PackageView::Explicit => "Explicit",
+            PackageView::Dependencies => "Dependencies",
+            PackageView::Orphans => "Orphans",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            PackageView::Explicit => PackageView::Dependencies,
+            PackageView::Dependencies => PackageView::Orphans,
+            PackageView::Orphans => PackageView::Explicit,
+        }
+    }
+
+    pub fn includes(&self, package: &InstalledPackage) -> bool {
+        match self {
+            PackageView::Explicit => package.explicit,
+            PackageView::Dependencies => !package.explicit,
+            PackageView::Orphans => !package.explicit && !package.required,
+        }
+    }
+}
+
+/// Key of the event of each package's most recent installation. Packages installed
+/// before the log begins are missing.
+pub fn installations(db: &LogDB) -> HashMap<String, LogKey> {
+    let mut installations = HashMap::new();
+    for (key, event) in &db.events {
+        if let LogEvent::Installed { package, .. } = event {
+            installations.insert(package.clone(), key.clone());
+        }
+    }
+    installations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logparse::parse_fixture;
+
+    fn package(name: &str, explicit: bool, required: bool) -> InstalledPackage {
+        InstalledPackage {
+            name: name.to_string(),
+            version: "1.0-1".to_string(),
+            explicit,
+            required,
+        }
+    }
+
+    #[test]
+    fn test_views() {
+        let packages = [
+            package("gelly", true, false),
+            package("libgelly", false, true),
+            package("gelly-docs", false, false),
+        ];
+        let names = |view: PackageView| -> Vec<&str> {
+            packages
+                .iter()
+                .filter(|package| view.includes(package))
+                .map(|package| package.name.as_str())
+                .collect()
+        };
+        assert_eq!(names(PackageView::Explicit), vec!["gelly"]);
+        assert_eq!(
+            names(PackageView::Dependencies),
+            vec!["libgelly", "gelly-docs"]
+        );
+        assert_eq!(names(PackageView::Orphans), vec!["gelly-docs"]);
+    }
+
+    #[test]
+    fn test_installations() {
+        let content = "\
+[2026-01-05T10:00:00+0000] [PACMAN] Running 'pacman -S gelly'
+[2026-01-05T10:00:10+0000] [ALPM] transaction started
+[2026-01-05T10:00:10+0000] [ALPM] installed libgelly (4.1.0-1)
+[2026-01-05T10:00:10+0000] [ALPM] installed gelly (4.1.0-1)
+[2026-01-05T10:00:11+0000] [ALPM] transaction completed
+[2026-01-20T10:00:00+0000] [PACMAN] Running 'pacman -Rs gelly'
+[2026-01-20T10:00:10+0000] [ALPM] transaction started
+[2026-01-20T10:00:10+0000] [ALPM] removed gelly (4.1.0-1)
+[2026-01-20T10:00:11+0000] [ALPM] transaction completed
+[2026-02-02T08:00:00+0000] [PACMAN] Running 'pacman -S gelly'
+[2026-02-02T08:00:10+0000] [ALPM] transaction started
+[2026-02-02T08:00:10+0000] [ALPM] installed gelly (4.1.1-1)
+[2026-02-02T08:00:11+0000] [ALPM] transaction completed
+";
+        let db = parse_fixture(content);
+        let installations = installations(&db);
+
+        assert_eq!(installations.len(), 2);
+        let first = db.transactions.values().next().unwrap();
+        let last = db.transactions.values().next_back().unwrap();
+        assert_eq!(db.transaction_for(&installations["libgelly"]), Some(first));
+        assert_eq!(db.transaction_for(&installations["gelly"]), Some(last));
+    }
+}
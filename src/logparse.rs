@@ -1,26 +1,245 @@
 //! Functions related to parsing pacman logs
 use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-use log::debug;
+use bincode::{Decode, Encode};
+use log::{debug, warn};
 use memchr::{memchr_iter, memmem};
+use serde::Serialize;
 use time::{OffsetDateTime, format_description::well_known::Iso8601};
 
-#[derive(Debug, PartialEq, Default)]
+use crate::logsource::LogFile;
+
+#[derive(Debug, PartialEq, Default, Encode, Decode)]
 pub struct LogDB {
     pub events: BTreeMap<LogKey, LogEvent>,
+    /// Transactions keyed by the key of their first event
+    pub transactions: BTreeMap<LogKey, Transaction>,
+    /// Lines that could not be parsed in tolerant mode
+    pub diagnostics: Vec<Diagnostic>,
+    /// Files the events were read from, oldest first
+    pub sources: Vec<LogSource>,
+    state: ParseState,
 }
 
 impl LogDB {
-    pub fn new(content: &str) -> Result<Self, ParseError> {
+    /// Parses a log and its rotated siblings as one continuous history. Files must be
+    /// ordered oldest first so that transactions spanning a rotation stay intact. Every
+    /// file is parsed to its end, so a live log that may end in a line still being written
+    /// is left to `append`.
+    pub fn from_files(files: &[LogFile], mode: ParseMode) -> Result<Self, ParseError> {
         let start = std::time::Instant::now();
-        let events = parse_log(content)?;
-        let duration = start.elapsed();
-        debug!("Parsed {} events in {:?}", events.len(), duration);
-        Ok(LogDB { events })
+        let mut db = LogDB::default();
+        for file in files {
+            db.start_source(&file.path);
+            LogParser::new(&mut db).parse(file.content.as_bytes(), mode, true)?;
+        }
+        debug!(
+            "Parsed {} events in {} transactions from {} files in {:?}",
+            db.events.len(),
+            db.transactions.len(),
+            db.sources.len(),
+            start.elapsed()
+        );
+        if !db.diagnostics.is_empty() {
+            warn!("{} lines could not be parsed", db.diagnostics.len());
+        }
+        Ok(db)
+    }
+
+    /// Starts a new file, e.g. once the log being followed has been rotated
+    pub fn start_source(&mut self, path: &Path) {
+        self.sources.push(LogSource {
+            path: path.to_path_buf(),
+            first: None,
+        });
+        self.state.line = 0;
+        self.state.offset = 0;
+    }
+
+    /// Records a rotated log that had to be skipped, so that the gap in the history
+    /// shows up with the other diagnostics
+    pub fn skipped(&mut self, path: &Path, error: &std::io::Error) {
+        self.diagnostics.push(Diagnostic {
+            path: Some(path.to_path_buf()),
+            line: None,
+            offset: 0,
+            error: ParseError::Unreadable(error.to_string()),
+            text: String::new(),
+        });
+    }
+
+    /// Parses lines appended to the most recent file, carrying on with its last
+    /// transaction. Only complete lines are parsed; the number of bytes consumed is
+    /// returned so that a partially written line can be retried once it is finished.
+    pub fn append(&mut self, content: &[u8], mode: ParseMode) -> Result<usize, ParseError> {
+        LogParser::new(self).parse(content, mode, false)
+    }
+
+    /// An otherwise empty history that parses on from where this one left off, holding
+    /// only the file and transaction appended lines carry on with. Lets appended lines
+    /// be parsed elsewhere and then merged back in.
+    pub fn continuation(&self) -> LogDB {
+        let transactions = self
+            .state
+            .current
+            .as_ref()
+            .and_then(|key| self.transactions.get_key_value(key))
+            .map(|(key, tx)| (key.clone(), tx.clone()))
+            .into_iter()
+            .collect();
+        LogDB {
+            events: BTreeMap::new(),
+            transactions,
+            diagnostics: Vec::new(),
+            sources: self.sources.last().cloned().into_iter().collect(),
+            state: self.state.clone(),
+        }
+    }
+
+    /// Adds what a continuation of this history parsed
+    pub fn merge(&mut self, tail: LogDB) {
+        // The tail starts with its own copy of the file this history ended with
+        if !tail.sources.is_empty() {
+            self.sources.pop();
+        }
+        self.sources.extend(tail.sources);
+        self.events.extend(tail.events);
+        self.transactions.extend(tail.transactions);
+        self.diagnostics.extend(tail.diagnostics);
+        self.state = tail.state;
+    }
+
+    /// Reconstructs what the log knows about a package, for packages that are no longer
+    /// installed or available anywhere else
+    pub fn package_summary(&self, package: &str) -> Option<PackageSummary> {
+        let mut summary: Option<PackageSummary> = None;
+        for (key, event) in self.events.iter() {
+            if event.package() != Some(package) {
+                continue;
+            }
+            let summary = summary.get_or_insert(PackageSummary {
+                first_installed: None,
+                last_version: String::new(),
+                last_event: key.timestamp,
+                removed: None,
+            });
+            summary.last_event = key.timestamp;
+            summary.removed = None;
+            match event {
+                LogEvent::Installed { version, .. } | LogEvent::Reinstalled { version, .. } => {
+                    summary.first_installed.get_or_insert(key.timestamp);
+                    summary.last_version.clone_from(version);
+                }
+                LogEvent::Upgraded { new_version, .. }
+                | LogEvent::Downgraded { new_version, .. } => {
+                    summary.last_version.clone_from(new_version);
+                }
+                LogEvent::Removed { version, .. } => {
+                    summary.last_version.clone_from(version);
+                    summary.removed = Some(key.timestamp);
+                }
+                _ => {}
+            }
+        }
+        summary
+    }
+
+    /// Every package event for a package, oldest first
+    pub fn package_history(&self, package: &str) -> Vec<(&LogKey, &LogEvent)> {
+        self.events
+            .iter()
+            .filter(|(_, event)| event.package() == Some(package))
+            .collect()
+    }
+
+    /// The transaction an event belongs to
+    pub fn transaction_for(&self, key: &LogKey) -> Option<&Transaction> {
+        self.transactions
+            .range(..=key)
+            .next_back()
+            .map(|(_, tx)| tx)
     }
+
+    /// A copy of the events with the given keys and of the transactions they belong to,
+    /// for work done on another thread
+    pub fn excerpt(&self, keys: &[LogKey]) -> LogDB {
+        let mut excerpt = LogDB::default();
+        for key in keys {
+            let Some(event) = self.events.get(key) else {
+                continue;
+            };
+            excerpt.events.insert(key.clone(), event.clone());
+            if let Some(tx) = self.transaction_for(key) {
+                excerpt
+                    .transactions
+                    .entry(tx.key.clone())
+                    .or_insert_with(|| tx.clone());
+            }
+        }
+        excerpt
+    }
+
+    /// The file an event was read from
+    pub fn source(&self, key: &LogKey) -> Option<&Path> {
+        self.sources
+            .iter()
+            .rev()
+            .find(|source| source.first.as_ref().is_some_and(|first| first <= key))
+            .map(|source| source.path.as_path())
+    }
+}
+
+/// Parses `content` strictly as a log named pacman.log, for tests elsewhere
+#[cfg(test)]
+pub fn parse_fixture(content: &str) -> LogDB {
+    let files = [LogFile {
+        path: PathBuf::from("pacman.log"),
+        content: content.to_string(),
+        size: 0,
+    }];
+    LogDB::from_files(&files, ParseMode::Strict).unwrap()
+}
+
+/// A package's history as far as the log can tell. Timestamps are those of the
+/// transaction the event belonged to.
+#[derive(Debug, PartialEq)]
+pub struct PackageSummary {
+    pub first_installed: Option<i64>,
+    pub last_version: String,
+    pub last_event: i64,
+    /// Set if the package's most recent event was its removal
+    pub removed: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, Clone, Encode, Decode)]
+pub struct LogSource {
+    pub path: PathBuf,
+    /// Key of the first event read from this file
+    pub first: Option<LogKey>,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Encode, Decode)]
+pub enum ParseMode {
+    /// Abort on the first line that fails to parse
+    Strict,
+    /// Record a diagnostic for lines that fail to parse and carry on
+    Tolerant,
+}
+
+/// A line that could not be parsed
+#[derive(Debug, PartialEq, Encode, Decode)]
+pub struct Diagnostic {
+    pub path: Option<PathBuf>,
+    /// 1-based line number, `None` for a whole file that could not be read
+    pub line: Option<usize>,
+    /// Byte offset of the start of the line
+    pub offset: usize,
+    pub error: ParseError,
+    pub text: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Encode, Decode)]
 pub struct LogKey {
     pub timestamp: i64,
     pub offset: usize,
@@ -40,12 +259,42 @@ impl PartialOrd for LogKey {
     }
 }
 
-#[derive(Debug, PartialEq)]
+/// Keys double as transaction ids on the command line, as unlike positions in the list
+/// they stay the same when logrotate expires old logs. The offset is left out when it is
+/// zero, as it is for all but transactions started within the same second.
+impl std::fmt::Display for LogKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        if self.offset == 0 {
+            write!(f, "{}", self.timestamp)
+        } else {
+            write!(f, "{}.{}", self.timestamp, self.offset)
+        }
+    }
+}
+
+impl std::str::FromStr for LogKey {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (timestamp, offset) = s.split_once('.').unwrap_or((s, "0"));
+        Ok(LogKey {
+            timestamp: timestamp.parse()?,
+            offset: offset.parse()?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Encode, Decode)]
+#[serde(tag = "kind", rename_all = "lowercase")]
 pub enum LogEvent {
     Installed {
         package: String,
         version: String,
     },
+    Reinstalled {
+        package: String,
+        version: String,
+    },
     Removed {
         package: String,
         version: String,
@@ -60,20 +309,129 @@ pub enum LogEvent {
         old_version: String,
         new_version: String,
     },
+    /// `[PACMAN] Running 'pacman -Syu'`
+    Command {
+        command: String,
+    },
+    /// `[PACMAN] synchronizing package lists`
+    #[serde(rename = "sync")]
+    SyncDatabases,
+    /// `[PACMAN] starting full system upgrade`
+    #[serde(rename = "sysupgrade")]
+    FullSystemUpgrade,
+    /// `[ALPM] running '20-systemd-sysusers.hook'...`
+    Hook {
+        name: String,
+    },
+    /// A line of output from an install scriptlet
+    Scriptlet {
+        output: String,
+    },
+    Warning {
+        message: String,
+    },
+    Error {
+        message: String,
+    },
 }
 
 impl LogEvent {
-    pub fn package(&self) -> &str {
+    /// The package this event applies to, if any
+    pub fn package(&self) -> Option<&str> {
         match self {
             LogEvent::Installed { package, .. }
+            | LogEvent::Reinstalled { package, .. }
             | LogEvent::Removed { package, .. }
             | LogEvent::Upgraded { package, .. }
-            | LogEvent::Downgraded { package, .. } => package,
+            | LogEvent::Downgraded { package, .. } => Some(package),
+            _ => None,
+        }
+    }
+
+    /// A short lowercase name for the kind of event
+    pub fn kind(&self) -> &'static str {
+        match self {
+            LogEvent::Installed { .. } => "installed",
+            LogEvent::Reinstalled { .. } => "reinstalled",
+            LogEvent::Removed { .. } => "removed",
+            LogEvent::Upgraded { .. } => "upgraded",
+            LogEvent::Downgraded { .. } => "downgraded",
+            LogEvent::Command { .. } => "command",
+            LogEvent::SyncDatabases => "sync",
+            LogEvent::FullSystemUpgrade => "sysupgrade",
+            LogEvent::Hook { .. } => "hook",
+            LogEvent::Scriptlet { .. } => "scriptlet",
+            LogEvent::Warning { .. } => "warning",
+            LogEvent::Error { .. } => "error",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Encode, Decode)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    /// The command never started a transaction, e.g. `pacman -Sy` or a declined prompt
+    NotStarted,
+    Completed,
+    /// Started and not finished as far as the log goes, either because pacman is still
+    /// running or because the log ends abruptly
+    InProgress,
+    /// Started but never completed, e.g. because pacman was killed or the machine crashed
+    Interrupted,
+    Failed,
+}
+
+impl TransactionStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransactionStatus::NotStarted => "not started",
+            TransactionStatus::InProgress => "in progress",
+            TransactionStatus::Completed => "completed",
+            TransactionStatus::Interrupted => "interrupted",
+            TransactionStatus::Failed => "failed",
+        }
+    }
+}
+
+/// Everything logged by a single pacman invocation: the command that triggered it, the
+/// transaction it ran and the hooks and scriptlets around it.
+#[derive(Debug, PartialEq, Clone, Serialize, Encode, Decode)]
+pub struct Transaction {
+    /// Key of the first event belonging to this transaction
+    pub key: LogKey,
+    pub start: i64,
+    pub end: i64,
+    pub command: Option<String>,
+    /// Keys of the package events in this transaction
+    pub packages: Vec<LogKey>,
+    pub hooks: Vec<String>,
+    pub scriptlet_output: Vec<String>,
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+    pub status: TransactionStatus,
+    /// Synthesized for package events that were logged without a `transaction started`
+    pub implicit: bool,
+}
+
+impl Transaction {
+    fn new(key: LogKey, timestamp: i64) -> Self {
+        Transaction {
+            key,
+            start: timestamp,
+            end: timestamp,
+            command: None,
+            packages: Vec::new(),
+            hooks: Vec::new(),
+            scriptlet_output: Vec::new(),
+            warnings: Vec::new(),
+            errors: Vec::new(),
+            status: TransactionStatus::NotStarted,
+            implicit: false,
         }
     }
 }
 
-#[derive(Debug, PartialEq, thiserror::Error)]
+#[derive(Debug, PartialEq, thiserror::Error, Encode, Decode)]
 pub enum ParseError {
     #[error("Failed to parse timestamp: {0}")]
     InvalidTimestamp(String),
@@ -81,76 +439,288 @@ pub enum ParseError {
     #[error("Failed to parse package info: {0}")]
     InvalidPackageInfo(String),
 
-    #[error("Invalid UTF-8 in log line")]
-    InvalidUtf8(#[from] std::str::Utf8Error),
-
-    #[error("Missing transaction start")]
-    MissingTransaction,
-}
-
-fn parse_log(content: &str) -> Result<BTreeMap<LogKey, LogEvent>, ParseError> {
-    let bytes = content.as_bytes();
-    let mut transactions: BTreeMap<LogKey, LogEvent> = BTreeMap::new();
-
-    let alpm_finder = memmem::Finder::new(b"[ALPM] ");
-    let tx_started = memmem::Finder::new(b"transaction started");
-    let tx_completed = memmem::Finder::new(b"transaction completed");
-    let installed = memmem::Finder::new(b"installed ");
-    let removed = memmem::Finder::new(b"removed ");
-    let upgraded = memmem::Finder::new(b"upgraded ");
-    let downgraded = memmem::Finder::new(b"downgraded ");
-    let warning = memmem::Finder::new(b"warning: ");
-
-    let mut current_transaction: Option<LogKey> = None;
-
-    let mut line_start = 0;
-    for line_end in memchr_iter(b'\n', bytes) {
-        let line = &bytes[line_start..line_end];
-
-        // We only care about lines containing [ALPM]
-        if let Some(alpm_pos) = alpm_finder.find(line) {
-            let timestamp = parse_timestamp(&line[..alpm_pos])?;
-            let after_alpm = &line[alpm_pos + 7..];
-
-            // check transaction boundaries
-            if tx_started.find(after_alpm).is_some() {
-                // new tx
-                current_transaction = Some(LogKey {
-                    timestamp,
-                    offset: 0,
-                });
-            } else if tx_completed.find(after_alpm).is_some() {
-                current_transaction = None;
-            } else {
-                let event = if warning.find(after_alpm).is_some() {
-                    None // skip for now, but messes with other matchers
-                } else if let Some(pos) = upgraded.find(after_alpm) {
-                    Some(parse_upgrade(&after_alpm[pos + 9..])?)
-                } else if let Some(pos) = installed.find(after_alpm) {
-                    Some(parse_installed(&after_alpm[pos + 10..])?)
-                } else if let Some(pos) = removed.find(after_alpm) {
-                    Some(parse_removed(&after_alpm[pos + 8..])?)
-                } else if let Some(pos) = downgraded.find(after_alpm) {
-                    Some(parse_downgrade(&after_alpm[pos + 11..])?)
-                } else {
-                    None
-                };
+    #[error("Failed to parse command: {0}")]
+    InvalidCommand(String),
+
+    #[error("Failed to parse hook: {0}")]
+    InvalidHook(String),
+
+    #[error("Invalid UTF-8 in log line after {valid_up_to} valid bytes of the message")]
+    InvalidUtf8 { valid_up_to: usize },
 
-                if let Some(event) = event {
-                    if let Some(log_key) = &mut current_transaction {
-                        transactions.insert(log_key.clone(), event);
-                        log_key.offset += 1;
+    #[error("Failed to read log: {0}")]
+    Unreadable(String),
+}
+
+// Only where the message stops being valid is kept, so that diagnostics can be kept in
+// the index
+impl From<std::str::Utf8Error> for ParseError {
+    fn from(error: std::str::Utf8Error) -> Self {
+        ParseError::InvalidUtf8 {
+            valid_up_to: error.valid_up_to(),
+        }
+    }
+}
+
+enum Tag {
+    Alpm,
+    Scriptlet,
+    Pacman,
+}
+
+/// Where parsing left off, so that lines appended to the log later carry on with the
+/// last transaction
+#[derive(Debug, PartialEq, Clone, Default, Encode, Decode)]
+struct ParseState {
+    /// Key of the transaction events are currently added to
+    current: Option<LogKey>,
+    next_key: Option<LogKey>,
+    in_transaction: bool,
+    /// Lines and bytes parsed so far from the current source
+    line: usize,
+    offset: usize,
+}
+
+/// Adds lines to a LogDB one at a time
+struct LogParser<'a> {
+    db: &'a mut LogDB,
+    alpm_finder: memmem::Finder<'static>,
+    scriptlet_finder: memmem::Finder<'static>,
+    pacman_finder: memmem::Finder<'static>,
+}
+
+impl<'a> LogParser<'a> {
+    fn new(db: &'a mut LogDB) -> Self {
+        LogParser {
+            db,
+            alpm_finder: memmem::Finder::new(b"[ALPM] "),
+            scriptlet_finder: memmem::Finder::new(b"[ALPM-SCRIPTLET] "),
+            pacman_finder: memmem::Finder::new(b"[PACMAN] "),
+        }
+    }
+
+    /// Parses `content` as the continuation of the current source and returns the number
+    /// of bytes consumed. The remainder after the last newline is only parsed if
+    /// `complete` is set.
+    fn parse(
+        &mut self,
+        content: &[u8],
+        mode: ParseMode,
+        complete: bool,
+    ) -> Result<usize, ParseError> {
+        let mut consumed = 0;
+        for line_end in memchr_iter(b'\n', content) {
+            self.parse_recorded(&content[consumed..line_end], mode)?;
+            consumed = line_end + 1;
+        }
+        // A log truncated mid-write may not end with a newline
+        if complete && consumed < content.len() {
+            self.parse_recorded(&content[consumed..], mode)?;
+            consumed = content.len();
+        }
+        Ok(consumed)
+    }
+
+    fn parse_recorded(&mut self, line: &[u8], mode: ParseMode) -> Result<(), ParseError> {
+        let result = self.parse_line(line);
+        let state = &mut self.db.state;
+        let offset = state.offset;
+        state.line += 1;
+        state.offset += line.len() + 1;
+        match (result, mode) {
+            (Ok(()), _) => {}
+            (Err(error), ParseMode::Strict) => return Err(error),
+            (Err(error), ParseMode::Tolerant) => self.db.diagnostics.push(Diagnostic {
+                path: self.db.sources.last().map(|source| source.path.clone()),
+                line: Some(self.db.state.line),
+                offset,
+                error,
+                text: String::from_utf8_lossy(line).to_string(),
+            }),
+        }
+        Ok(())
+    }
+
+    fn parse_line(&mut self, line: &[u8]) -> Result<(), ParseError> {
+        let (tag_pos, message, tag) = if let Some(pos) = self.alpm_finder.find(line) {
+            (pos, &line[pos + 7..], Tag::Alpm)
+        } else if let Some(pos) = self.scriptlet_finder.find(line) {
+            (pos, &line[pos + 17..], Tag::Scriptlet)
+        } else if let Some(pos) = self.pacman_finder.find(line) {
+            (pos, &line[pos + 9..], Tag::Pacman)
+        } else {
+            return Ok(());
+        };
+        let timestamp = parse_timestamp(&line[..tag_pos])?;
+
+        let event = match tag {
+            Tag::Scriptlet => LogEvent::Scriptlet {
+                output: parse_message(message)?,
+            },
+            _ if message.starts_with(b"warning: ") => LogEvent::Warning {
+                message: parse_message(&message[9..])?,
+            },
+            _ if message.starts_with(b"error: ") => LogEvent::Error {
+                message: parse_message(&message[7..])?,
+            },
+            Tag::Pacman => {
+                if message.starts_with(b"Running ") {
+                    // A malformed command must not leave an empty transaction behind
+                    let command = parse_command(&message[8..])?;
+                    self.start_transaction(timestamp);
+                    command
+                } else if message.starts_with(b"synchronizing package lists") {
+                    LogEvent::SyncDatabases
+                } else if message.starts_with(b"starting full system upgrade") {
+                    LogEvent::FullSystemUpgrade
+                } else {
+                    return Ok(());
+                }
+            }
+            Tag::Alpm => {
+                if message.starts_with(b"transaction started") {
+                    // A transaction without a preceding command starts its own group
+                    if self
+                        .current()
+                        .is_none_or(|tx| tx.implicit || tx.status != TransactionStatus::NotStarted)
+                    {
+                        self.start_transaction(timestamp);
+                    }
+                    self.db.state.in_transaction = true;
+                    self.end_transaction(timestamp, TransactionStatus::InProgress);
+                    return Ok(());
+                } else if message.starts_with(b"transaction completed") {
+                    self.db.state.in_transaction = false;
+                    self.end_transaction(timestamp, TransactionStatus::Completed);
+                    return Ok(());
+                } else if message.starts_with(b"transaction failed") {
+                    self.db.state.in_transaction = false;
+                    self.end_transaction(timestamp, TransactionStatus::Failed);
+                    return Ok(());
+                } else if message.starts_with(b"transaction interrupted") {
+                    self.db.state.in_transaction = false;
+                    self.end_transaction(timestamp, TransactionStatus::Interrupted);
+                    return Ok(());
+                } else if message.starts_with(b"running '") {
+                    parse_hook(&message[9..])?
+                } else {
+                    let event = if let Some(rest) = message.strip_prefix(b"upgraded ") {
+                        parse_upgrade(rest)?
+                    } else if let Some(rest) = message.strip_prefix(b"installed ") {
+                        parse_installed(rest)?
+                    } else if let Some(rest) = message.strip_prefix(b"reinstalled ") {
+                        parse_reinstalled(rest)?
+                    } else if let Some(rest) = message.strip_prefix(b"removed ") {
+                        parse_removed(rest)?
+                    } else if let Some(rest) = message.strip_prefix(b"downgraded ") {
+                        parse_downgrade(rest)?
                     } else {
-                        return Err(ParseError::MissingTransaction);
+                        return Ok(());
+                    };
+                    if !self.db.state.in_transaction {
+                        self.implicit_transaction(timestamp);
                     }
+                    event
                 }
             }
+        };
+
+        self.push(timestamp, event);
+        Ok(())
+    }
+
+    /// The transaction events are currently added to
+    fn current(&mut self) -> Option<&mut Transaction> {
+        let db = &mut *self.db;
+        let key = db.state.current.as_ref()?;
+        db.transactions.get_mut(key)
+    }
+
+    fn start_transaction(&mut self, timestamp: i64) {
+        // The previous transaction never got to log its end before the next command ran
+        if let Some(tx) = self.current()
+            && tx.status == TransactionStatus::InProgress
+        {
+            tx.status = TransactionStatus::Interrupted;
+        }
+        // Transactions started within the same second as the previous one carry on its
+        // offsets so that keys never collide.
+        let mut key = match self.db.state.next_key.take() {
+            Some(key) if key.timestamp == timestamp => key,
+            _ => LogKey {
+                timestamp,
+                offset: 0,
+            },
+        };
+        // A transaction without events leaves the offset where it was
+        while self.db.transactions.contains_key(&key) {
+            key.offset += 1;
+        }
+        let state = &mut self.db.state;
+        state.in_transaction = false;
+        state.current = Some(key.clone());
+        state.next_key = Some(key.clone());
+        self.db
+            .transactions
+            .insert(key.clone(), Transaction::new(key, timestamp));
+    }
+
+    /// Package events outside of a transaction, as found in logs rotated mid-transaction
+    /// or written by some AUR helpers, are collected into an implicit transaction.
+    fn implicit_transaction(&mut self, timestamp: i64) {
+        let attach = self
+            .current()
+            .is_some_and(|tx| tx.implicit || tx.status == TransactionStatus::NotStarted);
+        if !attach {
+            self.start_transaction(timestamp);
         }
+        if let Some(tx) = self.current() {
+            tx.implicit = true;
+        }
+    }
 
-        line_start = line_end + 1;
+    fn end_transaction(&mut self, timestamp: i64, status: TransactionStatus) {
+        if let Some(tx) = self.current() {
+            tx.end = timestamp;
+            tx.status = status;
+        }
     }
 
-    Ok(transactions)
+    fn push(&mut self, timestamp: i64, event: LogEvent) {
+        if self.db.state.current.is_none() {
+            self.start_transaction(timestamp);
+        }
+        let db = &mut *self.db;
+        let (Some(current), Some(next_key)) = (&db.state.current, &mut db.state.next_key) else {
+            unreachable!("a transaction was just started");
+        };
+        let key = next_key.clone();
+        next_key.offset += 1;
+
+        let Some(tx) = db.transactions.get_mut(current) else {
+            unreachable!("the current transaction is always in the map");
+        };
+        tx.end = timestamp;
+        match &event {
+            LogEvent::Command { command } => tx.command = Some(command.clone()),
+            LogEvent::Hook { name } => tx.hooks.push(name.clone()),
+            LogEvent::Scriptlet { output } => tx.scriptlet_output.push(output.clone()),
+            LogEvent::Warning { message } => tx.warnings.push(message.clone()),
+            LogEvent::Error { message } => tx.errors.push(message.clone()),
+            LogEvent::SyncDatabases | LogEvent::FullSystemUpgrade => {}
+            LogEvent::Installed { .. }
+            | LogEvent::Reinstalled { .. }
+            | LogEvent::Removed { .. }
+            | LogEvent::Upgraded { .. }
+            | LogEvent::Downgraded { .. } => tx.packages.push(key.clone()),
+        }
+        if let Some(source) = db.sources.last_mut()
+            && source.first.is_none()
+        {
+            source.first = Some(key.clone());
+        }
+        db.events.insert(key, event);
+    }
 }
 
 // pacman.log uses timestamps in the format: [2026-01-28T19:36:35-0800]
@@ -189,6 +759,15 @@ fn parse_removed(bytes: &[u8]) -> Result<LogEvent, ParseError> {
     })
 }
 
+fn parse_reinstalled(bytes: &[u8]) -> Result<LogEvent, ParseError> {
+    let s = std::str::from_utf8(bytes)?;
+    let (pkg, ver) = parse_package_version(s)?;
+    Ok(LogEvent::Reinstalled {
+        package: pkg.to_string(),
+        version: ver.to_string(),
+    })
+}
+
 // Upgrade looks like this: gelly (4.0.6-10 -> 4.1.0-1)
 fn parse_upgrade(bytes: &[u8]) -> Result<LogEvent, ParseError> {
     let s = std::str::from_utf8(bytes)?;
@@ -229,6 +808,33 @@ fn parse_downgrade(bytes: &[u8]) -> Result<LogEvent, ParseError> {
     })
 }
 
+// Command looks like this: 'pacman -S --needed base-devel'
+fn parse_command(bytes: &[u8]) -> Result<LogEvent, ParseError> {
+    let s = std::str::from_utf8(bytes)?;
+    let command = s
+        .strip_prefix('\'')
+        .and_then(|s| s.strip_suffix('\''))
+        .ok_or_else(|| ParseError::InvalidCommand(s.to_string()))?;
+    Ok(LogEvent::Command {
+        command: command.to_string(),
+    })
+}
+
+// Hook looks like this: 20-systemd-sysusers.hook'...
+fn parse_hook(bytes: &[u8]) -> Result<LogEvent, ParseError> {
+    let s = std::str::from_utf8(bytes)?;
+    let close = s
+        .rfind('\'')
+        .ok_or_else(|| ParseError::InvalidHook(s.to_string()))?;
+    Ok(LogEvent::Hook {
+        name: s[..close].to_string(),
+    })
+}
+
+fn parse_message(bytes: &[u8]) -> Result<String, ParseError> {
+    Ok(std::str::from_utf8(bytes)?.trim_end().to_string())
+}
+
 // version looks like: gelly (0.3.0-1)
 fn parse_package_version(s: &str) -> Result<(&str, &str), ParseError> {
     let open = s
@@ -244,6 +850,12 @@ fn parse_package_version(s: &str) -> Result<(&str, &str), ParseError> {
 mod tests {
     use super::*;
 
+    fn parse_log(content: &str, mode: ParseMode) -> Result<LogDB, ParseError> {
+        let mut db = LogDB::default();
+        LogParser::new(&mut db).parse(content.as_bytes(), mode, true)?;
+        Ok(db)
+    }
+
     #[test]
     fn test_parse_package_version() {
         assert_eq!(
@@ -275,4 +887,385 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("'pacman -S --needed base-devel'".as_bytes()),
+            Ok(LogEvent::Command {
+                command: "pacman -S --needed base-devel".to_string(),
+            })
+        );
+        assert_eq!(
+            parse_command(b"'pacman -S \xffgelly'"),
+            Err(ParseError::InvalidUtf8 { valid_up_to: 11 })
+        );
+    }
+
+    #[test]
+    fn test_parse_hook() {
+        assert_eq!(
+            parse_hook("20-systemd-sysusers.hook'...".as_bytes()),
+            Ok(LogEvent::Hook {
+                name: "20-systemd-sysusers.hook".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_log_categories() {
+        let log = "\
+[2026-01-28T19:36:35-0800] [PACMAN] Running 'pacman -Syu'
+[2026-01-28T19:36:35-0800] [PACMAN] synchronizing package lists
+[2026-01-28T19:36:38-0800] [PACMAN] starting full system upgrade
+[2026-01-28T19:36:50-0800] [ALPM] running '60-mkinitcpio-remove.hook'...
+[2026-01-28T19:36:50-0800] [ALPM] transaction started
+[2026-01-28T19:36:50-0800] [ALPM] reinstalled gelly (4.1.0-1)
+[2026-01-28T19:36:50-0800] [ALPM] warning: /etc/pacman.conf installed as /etc/pacman.conf.pacnew
+[2026-01-28T19:36:50-0800] [ALPM-SCRIPTLET] ==> Rebuilding initramfs
+[2026-01-28T19:36:51-0800] [ALPM] transaction completed
+[2026-01-28T19:36:51-0800] [ALPM] error: command failed to execute correctly
+[2026-01-28T19:40:00-0800] [PACMAN] Running 'pacman -Sy'
+";
+        let events = parse_log(log, ParseMode::Strict).unwrap().events;
+        let start = 1769657795;
+        let values: Vec<_> = events.values().collect();
+        assert_eq!(
+            values,
+            vec![
+                &LogEvent::Command {
+                    command: "pacman -Syu".to_string()
+                },
+                &LogEvent::SyncDatabases,
+                &LogEvent::FullSystemUpgrade,
+                &LogEvent::Hook {
+                    name: "60-mkinitcpio-remove.hook".to_string()
+                },
+                &LogEvent::Reinstalled {
+                    package: "gelly".to_string(),
+                    version: "4.1.0-1".to_string(),
+                },
+                &LogEvent::Warning {
+                    message: "/etc/pacman.conf installed as /etc/pacman.conf.pacnew".to_string()
+                },
+                &LogEvent::Scriptlet {
+                    output: "==> Rebuilding initramfs".to_string()
+                },
+                &LogEvent::Error {
+                    message: "command failed to execute correctly".to_string()
+                },
+                &LogEvent::Command {
+                    command: "pacman -Sy".to_string()
+                },
+            ]
+        );
+        // Everything up to the second command belongs to the first group
+        let keys: Vec<_> = events.keys().collect();
+        assert_eq!(
+            keys[7],
+            &LogKey {
+                timestamp: start,
+                offset: 7
+            }
+        );
+        assert_eq!(keys[8].offset, 0);
+    }
+
+    #[test]
+    fn test_parse_log_implicit_transaction() {
+        let log = "\
+[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)
+[2026-01-28T19:36:51-0800] [ALPM] installed puckrat (0.1.0-1)
+[2026-01-28T19:36:52-0800] [ALPM] transaction started
+[2026-01-28T19:36:52-0800] [ALPM] removed gelly (4.1.0-1)
+[2026-01-28T19:36:53-0800] [ALPM] transaction completed
+[2026-01-28T19:37:00-0800] [ALPM] upgraded puckrat (0.1.0-1 -> 0.2.0-1)
+";
+        let db = parse_log(log, ParseMode::Strict).unwrap();
+        let txs: Vec<_> = db.transactions.values().collect();
+        assert_eq!(txs.len(), 3);
+        assert!(txs[0].implicit);
+        assert_eq!(txs[0].packages.len(), 2);
+        assert!(!txs[1].implicit);
+        assert_eq!(txs[1].status, TransactionStatus::Completed);
+        assert!(txs[2].implicit);
+        assert_eq!(txs[2].start, txs[2].key.timestamp);
+    }
+
+    #[test]
+    fn test_parse_log_tolerant() {
+        let log = "\
+[2026-01-28T19:36:50-0800] [ALPM] transaction started
+[2026-01-28T19:36:50-0800] [ALPM] installed gelly 4.1.0-1
+[2026-01-28T19:36:50-0800] [ALPM] installed puckrat (0.1.0-1)
+[2026-01-28T19:37:00-0800] [ALPM] upgraded puckrat (0.1.0-1 -> 0.1";
+        assert_eq!(
+            parse_log(log, ParseMode::Strict),
+            Err(ParseError::InvalidPackageInfo("gelly 4.1.0-1".to_string()))
+        );
+
+        let db = parse_log(log, ParseMode::Tolerant).unwrap();
+        assert_eq!(db.events.len(), 1);
+        assert_eq!(db.diagnostics.len(), 2);
+        assert_eq!(db.diagnostics[0].line, Some(2));
+        assert_eq!(db.diagnostics[0].offset, 54);
+        assert_eq!(
+            db.diagnostics[0].text,
+            "[2026-01-28T19:36:50-0800] [ALPM] installed gelly 4.1.0-1"
+        );
+        assert_eq!(db.diagnostics[1].line, Some(4));
+        assert_eq!(
+            db.diagnostics[1].error,
+            ParseError::InvalidPackageInfo("puckrat (0.1.0-1 -> 0.1".to_string())
+        );
+    }
+
+    #[test]
+    fn test_from_files_rotated_mid_transaction() {
+        let files = [
+            LogFile {
+                path: PathBuf::from("pacman.log.1"),
+                content: "\
+[2026-01-28T19:36:35-0800] [PACMAN] Running 'pacman -Syu'
+[2026-01-28T19:36:50-0800] [ALPM] transaction started
+[2026-01-28T19:36:50-0800] [ALPM] upgraded gelly (4.0.6-10 -> 4.1.0-1)
+"
+                .to_string(),
+                size: 0,
+            },
+            LogFile {
+                path: PathBuf::from("pacman.log"),
+                content: "\
+[2026-01-28T19:36:51-0800] [ALPM] upgraded puckrat (0.1.0-1 -> 0.2.0-1)
+[2026-01-28T19:36:51-0800] [ALPM] transaction completed
+"
+                .to_string(),
+                size: 0,
+            },
+        ];
+        let db = LogDB::from_files(&files, ParseMode::Strict).unwrap();
+        assert_eq!(db.transactions.len(), 1);
+        let tx = db.transactions.values().next().unwrap();
+        assert_eq!(tx.status, TransactionStatus::Completed);
+        assert_eq!(tx.packages.len(), 2);
+        assert_eq!(db.source(&tx.packages[0]), Some(Path::new("pacman.log.1")));
+        assert_eq!(db.source(&tx.packages[1]), Some(Path::new("pacman.log")));
+    }
+
+    #[test]
+    fn test_package_summary() {
+        let log = "\
+[2026-01-28T19:36:50-0800] [ALPM] transaction started
+[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.0.6-10)
+[2026-01-28T19:36:50-0800] [ALPM] transaction completed
+[2026-02-01T10:00:00-0800] [ALPM] transaction started
+[2026-02-01T10:00:00-0800] [ALPM] upgraded gelly (4.0.6-10 -> 4.1.0-1)
+[2026-02-01T10:00:00-0800] [ALPM] transaction completed
+[2026-02-03T10:00:00-0800] [ALPM] transaction started
+[2026-02-03T10:00:00-0800] [ALPM] removed gelly (4.1.0-1)
+[2026-02-03T10:00:00-0800] [ALPM] transaction completed
+";
+        let db = parse_log(log, ParseMode::Strict).unwrap();
+        assert_eq!(
+            db.package_summary("gelly"),
+            Some(PackageSummary {
+                first_installed: Some(1769657810),
+                last_version: "4.1.0-1".to_string(),
+                last_event: 1770141600,
+                removed: Some(1770141600),
+            })
+        );
+        assert_eq!(db.package_summary("puckrat"), None);
+
+        let history = db.package_history("gelly");
+        assert_eq!(history.len(), 3);
+        let tx = db.transaction_for(history[1].0).unwrap();
+        assert_eq!(tx.start, 1769968800);
+        assert_eq!(tx.packages, vec![history[1].0.clone()]);
+    }
+
+    #[test]
+    fn test_parse_log_tolerant_command() {
+        let log = "\
+[2026-01-28T19:36:35-0800] [PACMAN] Running 'pacman -S gelly'
+[2026-01-28T19:36:50-0800] [ALPM] transaction started
+[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)
+[2026-01-28T19:36:51-0800] [ALPM] transaction completed
+[2026-01-28T19:40:00-0800] [PACMAN] Running 'pacman -S puckrat
+[2026-01-28T19:40:10-0800] [ALPM] installed puckrat (0.1.0-1)
+";
+        let db = parse_log(log, ParseMode::Tolerant).unwrap();
+        assert_eq!(db.diagnostics.len(), 1);
+        let txs: Vec<_> = db.transactions.values().collect();
+        assert_eq!(txs.len(), 2);
+        assert_eq!(txs[1].command, None);
+        assert!(txs[1].implicit);
+        assert_eq!(txs[1].start, 1769658010);
+    }
+
+    #[test]
+    fn test_parse_log_empty_transaction() {
+        let log = "\
+[2026-01-28T19:36:50-0800] [ALPM] transaction started
+[2026-01-28T19:36:50-0800] [ALPM] transaction completed
+[2026-01-28T19:36:50-0800] [ALPM] transaction started
+[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)
+[2026-01-28T19:36:51-0800] [ALPM] transaction completed
+";
+        let db = parse_log(log, ParseMode::Strict).unwrap();
+        let txs: Vec<_> = db.transactions.values().collect();
+        assert_eq!(txs.len(), 2);
+        assert!(txs[0].packages.is_empty());
+        assert_eq!(txs[1].packages.len(), 1);
+        assert_eq!(db.transaction_for(&txs[1].packages[0]), Some(txs[1]));
+    }
+
+    #[test]
+    fn test_parse_log_transactions() {
+        let log = "\
+[2026-01-28T19:36:35-0800] [PACMAN] Running 'pacman -S gelly'
+[2026-01-28T19:36:50-0800] [ALPM] transaction started
+[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)
+[2026-01-28T19:36:51-0800] [ALPM] transaction completed
+[2026-01-28T19:36:52-0800] [ALPM] running '30-systemd-update.hook'...
+[2026-01-28T19:40:00-0800] [PACMAN] Running 'pacman -Sy'
+[2026-01-28T19:45:00-0800] [PACMAN] Running 'pacman -R gelly'
+[2026-01-28T19:45:01-0800] [ALPM] transaction started
+[2026-01-28T19:45:01-0800] [ALPM] removed gelly (4.1.0-1)
+[2026-01-28T19:45:01-0800] [ALPM] transaction failed
+[2026-01-28T19:50:00-0800] [ALPM] transaction started
+[2026-01-28T19:50:00-0800] [ALPM] installed gelly (4.1.0-1)
+";
+        let db = parse_log(log, ParseMode::Strict).unwrap();
+        let txs: Vec<_> = db.transactions.values().collect();
+        assert_eq!(txs.len(), 4);
+
+        assert_eq!(txs[0].command.as_deref(), Some("pacman -S gelly"));
+        assert_eq!(txs[0].status, TransactionStatus::Completed);
+        assert_eq!(txs[0].end - txs[0].start, 17);
+        assert_eq!(txs[0].hooks, vec!["30-systemd-update.hook".to_string()]);
+        assert_eq!(
+            db.events[&txs[0].packages[0]],
+            LogEvent::Installed {
+                package: "gelly".to_string(),
+                version: "4.1.0-1".to_string(),
+            }
+        );
+
+        assert_eq!(txs[1].status, TransactionStatus::NotStarted);
+        assert!(txs[1].packages.is_empty());
+        assert_eq!(txs[2].status, TransactionStatus::Failed);
+        assert_eq!(txs[3].command, None);
+        // Nothing after the last transaction says how it ended
+        assert_eq!(txs[3].status, TransactionStatus::InProgress);
+        assert_eq!(txs[3].packages.len(), 1);
+    }
+
+    #[test]
+    fn test_excerpt() {
+        let db = parse_log(
+            "\
+[2026-01-28T19:36:35-0800] [PACMAN] Running 'pacman -S gelly'
+[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)
+[2026-01-29T10:00:00-0800] [PACMAN] Running 'pacman -Syu'
+[2026-01-29T10:00:10-0800] [ALPM] upgraded gelly (4.1.0-1 -> 4.1.1-1)
+[2026-01-29T10:00:10-0800] [ALPM] installed puckrat (0.1.0-1)
+",
+            ParseMode::Strict,
+        )
+        .unwrap();
+        let keys: Vec<LogKey> = db.events.keys().skip(3).cloned().collect();
+        let excerpt = db.excerpt(&keys);
+        assert_eq!(excerpt.events.len(), 2);
+        let txs: Vec<_> = excerpt.transactions.values().collect();
+        assert_eq!(txs, vec![db.transactions.values().next_back().unwrap()]);
+        assert_eq!(excerpt.transaction_for(&keys[1]), Some(txs[0]));
+    }
+
+    #[test]
+    fn test_merge() {
+        let first = "\
+[2026-01-28T19:36:35-0800] [PACMAN] Running 'pacman -S gelly'
+[2026-01-28T19:36:50-0800] [ALPM] transaction started
+";
+        let second = "\
+[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)
+[2026-01-28T19:36:51-0800] [ALPM] transaction completed
+";
+        let rotated = "[2026-01-29T10:00:00-0800] [PACMAN] Running 'pacman -Syu'\n";
+        let file = |content: String| LogFile {
+            path: PathBuf::from("pacman.log"),
+            content,
+            size: 0,
+        };
+        let expected = LogDB::from_files(
+            &[
+                file(format!("{}{}", first, second)),
+                file(rotated.to_string()),
+            ],
+            ParseMode::Strict,
+        )
+        .unwrap();
+
+        let mut db = LogDB::from_files(&[file(first.to_string())], ParseMode::Strict).unwrap();
+        let mut tail = db.continuation();
+        tail.append(second.as_bytes(), ParseMode::Strict).unwrap();
+        let mut next = tail.continuation();
+        db.merge(tail);
+        next.start_source(Path::new("pacman.log"));
+        next.append(rotated.as_bytes(), ParseMode::Strict).unwrap();
+        db.merge(next);
+        assert_eq!(db, expected);
+    }
+
+    #[test]
+    fn test_append() {
+        let mut db = parse_log(
+            "\
+[2026-01-28T19:36:35-0800] [PACMAN] Running 'pacman -S gelly'
+[2026-01-28T19:36:50-0800] [ALPM] transaction started
+",
+            ParseMode::Strict,
+        )
+        .unwrap();
+        let tx = db.transactions.values().next().unwrap();
+        assert_eq!(tx.status, TransactionStatus::InProgress);
+
+        // The half-written line is left for the next call
+        let appended = "\
+[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)
+[2026-01-28T19:36:51-0800] [ALPM] transaction comp";
+        let consumed = db.append(appended.as_bytes(), ParseMode::Strict).unwrap();
+        assert_eq!(
+            &appended[consumed..],
+            "[2026-01-28T19:36:51-0800] [ALPM] transaction comp"
+        );
+        let consumed = db
+            .append(
+                "[2026-01-28T19:36:51-0800] [ALPM] transaction completed\n".as_bytes(),
+                ParseMode::Strict,
+            )
+            .unwrap();
+        assert_eq!(consumed, 56);
+
+        let txs: Vec<_> = db.transactions.values().collect();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].status, TransactionStatus::Completed);
+        assert_eq!(txs[0].packages.len(), 1);
+
+        // A transaction cut short by the next command was interrupted
+        db.append(
+            "\
+[2026-01-28T19:40:00-0800] [ALPM] transaction started
+[2026-01-28T19:41:00-0800] [PACMAN] Running 'pacman -Syu'
+"
+            .as_bytes(),
+            ParseMode::Strict,
+        )
+        .unwrap();
+        let txs: Vec<_> = db.transactions.values().collect();
+        assert_eq!(txs.len(), 3);
+        assert_eq!(txs[1].status, TransactionStatus::Interrupted);
+        assert_eq!(txs[2].status, TransactionStatus::NotStarted);
+    }
 }
@@ -0,0 +1,214 @@
+//! Discovery and reading of pacman logs, including the siblings left behind by
+//! logrotate such as pacman.log.1, pacman.log.2.gz or pacman.log-20260101.zst
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use log::{debug, warn};
+
+#[derive(Debug)]
+pub struct LogFile {
+    pub path: PathBuf,
+    pub content: String,
+    /// Number of bytes read, which is where following the file picks up
+    pub size: u64,
+}
+
+/// The readable files of a log's history, and the rotated ones that could not be read
+#[derive(Debug, Default)]
+pub struct History {
+    pub files: Vec<LogFile>,
+    pub unreadable: Vec<(PathBuf, io::Error)>,
+}
+
+/// Position of a log file in the rotation, ordered oldest first
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Rotation {
+    /// dateext style: pacman.log-20260101
+    Dated(String),
+    /// Numbered style, where higher numbers are older: pacman.log.2
+    Numbered(std::cmp::Reverse<u32>),
+    /// The live log itself
+    Current,
+}
+
+/// Reads the log at `path` along with all of its rotated siblings, oldest first. A
+/// rotated sibling that is corrupt, or whose decompressor is missing, is skipped rather
+/// than keeping the live log from loading.
+pub fn read_history(path: &Path) -> io::Result<History> {
+    let mut history = History::default();
+    for file in discover(path)? {
+        let bytes = match read_log(&file) {
+            Ok(bytes) => bytes,
+            Err(error) if file.file_name() != path.file_name() => {
+                warn!("Skipping {}: {}", file.display(), error);
+                history.unreadable.push((file, error));
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        history.files.push(LogFile {
+            // A stray invalid byte should not make the whole history unreadable
+            content: String::from_utf8_lossy(&bytes).into_owned(),
+            size: bytes.len() as u64,
+            path: file,
+        });
+    }
+    Ok(history)
+}
+
+/// Finds the log at `path` and its rotated siblings, oldest first
+pub fn discover(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let Some(base) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(vec![path.to_path_buf()]);
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    // Siblings are named the way `path` was given, so that the live log compares equal
+    // to it
+    let mut logs = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        if let Some(name) = entry.file_name().to_str()
+            && let Some(rotation) = rotation(base, name)
+        {
+            logs.push((rotation, path.with_file_name(name)));
+        }
+    }
+    // The live log may be missing if it was just rotated away
+    if !logs
+        .iter()
+        .any(|(rotation, _)| *rotation == Rotation::Current)
+        && path.exists()
+    {
+        logs.push((Rotation::Current, path.to_path_buf()));
+    }
+    logs.sort();
+    debug!("Found {} log files for {}", logs.len(), path.display());
+    Ok(logs.into_iter().map(|(_, path)| path).collect())
+}
+
+fn rotation(base: &str, name: &str) -> Option<Rotation> {
+    let suffix = name.strip_prefix(base)?;
+    if suffix.is_empty() {
+        return Some(Rotation::Current);
+    }
+    let suffix = ["gz", "xz", "zst"]
+        .iter()
+        .find_map(|ext| suffix.strip_suffix(ext)?.strip_suffix('.'))
+        .unwrap_or(suffix);
+    if let Some(number) = suffix.strip_prefix('.') {
+        number
+            .parse()
+            .ok()
+            .map(|number| Rotation::Numbered(std::cmp::Reverse(number)))
+    } else if let Some(date) = suffix.strip_prefix('-')
+        && !date.is_empty()
+        && date.bytes().all(|b| b.is_ascii_digit())
+    {
+        Some(Rotation::Dated(date.to_string()))
+    } else {
+        None
+    }
+}
+
+/// Reads a single log file, decompressing it if needed
+pub fn read_log(path: &Path) -> io::Result<Vec<u8>> {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("gz") => decompress("gzip", path),
+        Some("xz") => decompress("xz", path),
+        Some("zst") => decompress("zstd", path),
+        _ => std::fs::read(path),
+    }
+}
+
+// gzip, xz and zstd are all part of a base Arch install, and pacman itself depends on
+// the latter two
+fn decompress(program: &str, path: &Path) -> io::Result<Vec<u8>> {
+    let output = Command::new(program).arg("-dc").arg(path).output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "{} failed to decompress {}: {}",
+            program,
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_dir;
+
+    #[test]
+    fn test_rotation() {
+        assert_eq!(
+            rotation("pacman.log", "pacman.log"),
+            Some(Rotation::Current)
+        );
+        assert_eq!(
+            rotation("pacman.log", "pacman.log.2.gz"),
+            Some(Rotation::Numbered(std::cmp::Reverse(2)))
+        );
+        assert_eq!(
+            rotation("pacman.log", "pacman.log-20260101.zst"),
+            Some(Rotation::Dated("20260101".to_string()))
+        );
+        assert_eq!(rotation("pacman.log", "pacman.log.pacsave"), None);
+        assert_eq!(rotation("pacman.log", "other.log.1"), None);
+    }
+
+    #[test]
+    fn test_read_history_unreadable() {
+        let dir = temp_dir("logsource");
+        let path = dir.join("pacman.log");
+        std::fs::write(&path, "live\n").unwrap();
+        std::fs::write(dir.join("pacman.log.1.gz"), "not gzip").unwrap();
+        std::fs::write(dir.join("pacman.log.2"), "oldest\n").unwrap();
+
+        let history = read_history(&path).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(history.files.last().map(|file| &file.path), Some(&path));
+        let contents: Vec<&str> = history
+            .files
+            .iter()
+            .map(|file| file.content.as_str())
+            .collect();
+        assert_eq!(contents, vec!["oldest\n", "live\n"]);
+        assert_eq!(history.unreadable.len(), 1);
+        assert_eq!(history.unreadable[0].0, dir.join("pacman.log.1.gz"));
+    }
+
+    #[test]
+    fn test_discover_relative() {
+        assert_eq!(
+            discover(Path::new("Cargo.toml")).unwrap(),
+            vec![PathBuf::from("Cargo.toml")]
+        );
+    }
+
+    #[test]
+    fn test_rotation_order() {
+        let mut names = vec![
+            "pacman.log",
+            "pacman.log.1",
+            "pacman.log.3.xz",
+            "pacman.log.2.gz",
+        ];
+        names.sort_by_key(|name| rotation("pacman.log", name));
+        assert_eq!(
+            names,
+            vec![
+                "pacman.log.3.xz",
+                "pacman.log.2.gz",
+                "pacman.log.1",
+                "pacman.log"
+            ]
+        );
+    }
+}
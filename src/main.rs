@@ -1,21 +1,78 @@
 mod alpmutil;
 mod app;
+mod cli;
+mod commands;
+mod compare;
+mod deptree;
 mod event;
+mod export;
+mod filter;
+mod follow;
+mod format;
+mod index;
+mod infocache;
+mod installed;
 mod logparse;
+mod logsource;
 mod message;
 mod model;
+mod pacmanconf;
+mod pkgcache;
+mod rollback;
+mod snapshot;
+mod stats;
+#[cfg(test)]
+mod testutil;
 mod view;
+mod worker;
+
+use std::sync::mpsc;
 
 use model::Model;
 
 use crate::alpmutil::AlpmService;
+use crate::cli::Args;
+use crate::logparse::ParseMode;
+use crate::pacmanconf::PacmanConfig;
+use crate::worker::AlpmWorker;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     env_logger::init();
-    let alpm_service = AlpmService::new()?;
-    let path = "/var/log/pacman.log";
-    let log_content = std::fs::read_to_string(path)?;
-    let model = Model::new(alpm_service, &log_content)?;
-    ratatui::run(|terminal| app::run(model, terminal))?;
+    let args = Args::parse_checked();
+    let config = PacmanConfig::load_or_default(&args.config(), &|path| args.in_sysroot(path))?;
+    let mode = if args.strict {
+        ParseMode::Strict
+    } else {
+        ParseMode::Tolerant
+    };
+    let (root, dbpath, cache_dirs) = (
+        args.root(&config),
+        args.dbpath(&config),
+        args.cache_dirs(&config),
+    );
+
+    if let Some(command) = &args.command {
+        let log = args.log(&config);
+        let (logs, _) = index::load(&log, index::index_path(&log).as_deref(), mode)?;
+        commands::run(command, &logs, &|| {
+            AlpmService::new(&root, &dbpath, cache_dirs.clone(), &config)
+        })?;
+        return Ok(());
+    }
+
+    let (sender, messages) = mpsc::channel();
+    let log = args.log(&config);
+    let index = index::index_path(&log);
+    worker::spawn_log_loader(sender.clone(), log, index, mode, args.follow);
+    let alpm = AlpmWorker::spawn(sender.clone(), move || {
+        AlpmService::new(&root, &dbpath, cache_dirs.clone(), &config)
+    })?;
+    event::spawn_ticks(sender.clone(), event::TICK_RATE);
+
+    let model = Model::new(alpm, sender.clone());
+    ratatui::run(|terminal| {
+        event::spawn_input(sender);
+        app::run(model, terminal, messages)
+    })?;
     Ok(())
 }
@@ -1,6 +1,84 @@
-#[derive(PartialEq)]
+use std::path::PathBuf;
+
+use ratatui::crossterm::event::KeyEvent;
+
+use crate::alpmutil::{DbChange, Dependencies, PackageInfo};
+use crate::compare::Comparison;
+use crate::export::ExportFormat;
+use crate::installed::InstalledPackage;
+use crate::logparse::LogDB;
+use crate::rollback::RollbackPlan;
+
 pub enum Message {
+    /// A key press, mapped to one of the messages below depending on the screen
+    Key(KeyEvent),
+    /// The terminal was resized and needs to be drawn again
+    Resize,
+    /// Sent at a fixed rate to drive animations
+    Tick,
+    /// The parsed history, and whether the live log is being followed
+    LogsLoaded(Box<LogDB>, bool),
+    LoadFailed(String),
+    /// Lines appended to the live log, parsed as a continuation of the history
+    LogAppended(Box<LogDB>),
+    FollowFailed(String),
+    /// The answer to a package lookup, `None` if alpm does not know the package
+    PackageInfoLoaded(String, Option<Box<PackageInfo>>),
+    /// The answer to a dependency lookup, `None` if alpm does not know the package
+    DependenciesLoaded(String, Option<Box<Dependencies>>),
+    /// pacman wrote the databases, so earlier answers may be stale
+    DatabasesChanged(DbChange),
+    /// A rollback plan with its package files located in the cache
+    RollbackPlanned(Box<RollbackPlan>),
+    /// A comparison with its installed sizes looked up
+    ComparisonSized(Box<Comparison>),
+    /// Every package in the local database
+    LocalPackagesLoaded(Vec<InstalledPackage>),
+    /// The file the visible events were exported to, and how many there were
+    Exported(PathBuf, usize),
+    ExportFailed(String),
     MoveUp,
     MoveDown,
+    ShowDiagnostics,
+    ShowHistory,
+    /// Show everything known about the focused package
+    ShowPackage,
+    /// Expand or collapse the selected node of the dependency tree
+    ToggleDependency,
+    /// Show the package of the selected node of the dependency tree
+    OpenDependency,
+    /// Move the focus between the panes of the package screen
+    SwitchPane,
+    ShowStats,
+    /// Show the installed packages by why they were installed
+    ShowPackages,
+    /// Show the next group of installed packages
+    SwitchView,
+    /// Show the keys for the current screen
+    ShowHelp,
+    /// Plan a rollback of the selected event's transaction
+    ShowRollback,
+    WriteRollbackScript,
+    /// Mark the selected event's transaction, comparing it with the one marked before
+    MarkCompare,
+    /// Ask for a date to mark as one end of a comparison
+    StartDateMark,
+    DateInput(char),
+    DateBackspace,
+    ConfirmDate,
+    CancelDate,
+    StartFilter,
+    FilterInput(char),
+    FilterBackspace,
+    ConfirmFilter,
+    CancelFilter,
+    /// Select the next or previous event matching the filter
+    NextMatch,
+    PreviousMatch,
+    /// Ask which format to export the visible events in
+    StartExport,
+    Export(ExportFormat),
+    CancelExport,
+    Back,
     Quit,
 }
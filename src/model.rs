@@ -1,7 +1,28 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+
 use ratatui::widgets::ListState;
+use time::{OffsetDateTime, macros::format_description};
+
+use crate::alpmutil::{DbChange, Dependencies, PackageInfo};
+use crate::compare::{self, Comparison};
+use crate::deptree::{self, DependencyCache, NodePath, Row};
+use crate::export::ExportFormat;
+use crate::filter::{self, Filter};
+use crate::format::render_timestamp;
+use crate::infocache::PackageInfoCache;
+use crate::installed::{self, InstalledPackage, PackageView};
+use crate::logparse::{LogDB, LogEvent, LogKey};
+use crate::message::Message;
+use crate::rollback::RollbackPlan;
+use crate::stats::Stats;
+use crate::worker::{self, AlpmWorker};
 
-use crate::alpmutil::AlpmService;
-use crate::logparse::{LogDB, LogEvent, LogKey, ParseError};
+/// Events on either side of the selected one whose packages are looked up ahead
+const PREFETCH: usize = 3;
 
 #[derive(Debug, Default, PartialEq, Eq)]
 pub enum RunningState {
@@ -10,34 +31,266 @@ pub enum RunningState {
     Done,
 }
 
+/// A screen on the navigation stack. Each holds its own state, so it is as it was left
+/// when the screens pushed on top of it are popped.
+#[derive(Debug, Default)]
+pub enum Screen {
+    /// The transaction list, always at the bottom of the stack. Its state is kept in the
+    /// model as following and filtering change it.
+    #[default]
+    Main,
+    /// Everything known about a single package, with its dependency tree
+    Package {
+        package: String,
+        expanded: HashSet<NodePath>,
+        state: ListState,
+        /// The pane moving up and down applies to
+        focus: PackagePane,
+        /// Lines the details pane is scrolled down by
+        scroll: u16,
+    },
+    Diagnostics(ListState),
+    /// Every event for a single package
+    History {
+        package: String,
+        state: ListState,
+    },
+    /// Rollback of the transaction with this key. The plan is set once its packages have
+    /// been looked for in the cache.
+    Rollback {
+        transaction: LogKey,
+        plan: Option<RollbackPlan>,
+        state: ListState,
+    },
+    /// Net change between two marked transactions
+    Compare {
+        comparison: Comparison,
+        state: ListState,
+    },
+    /// Computed when the screen is opened
+    Stats(Box<Stats>),
+    /// Installed packages by why they were installed. The packages are set once the
+    /// local database has been read.
+    Packages {
+        view: PackageView,
+        packages: Option<Vec<InstalledPackage>>,
+        /// Most recent installation of each package according to the log
+        installations: HashMap<String, LogKey>,
+        state: ListState,
+    },
+    /// Keys for the screen below
+    Help,
+}
+
+impl Screen {
+    pub fn package(package: String) -> Self {
+        Screen::Package {
+            package,
+            expanded: HashSet::new(),
+            state: ListState::default().with_selected(Some(0)),
+            focus: PackagePane::default(),
+            scroll: 0,
+        }
+    }
+
+    /// The list that moving up and down selects in, if the screen has one of its own
+    pub fn list_state(&mut self) -> Option<&mut ListState> {
+        match self {
+            Screen::Package {
+                state,
+                focus: PackagePane::Dependencies,
+                ..
+            }
+            | Screen::Diagnostics(state)
+            | Screen::History { state, .. }
+            | Screen::Rollback { state, .. }
+            | Screen::Compare { state, .. }
+            | Screen::Packages { state, .. } => Some(state),
+            Screen::Main | Screen::Package { .. } | Screen::Stats(_) | Screen::Help => None,
+        }
+    }
+}
+
+/// The two panes of the package screen
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PackagePane {
+    Details,
+    #[default]
+    Dependencies,
+}
+
 #[derive(Debug)]
 pub struct Model {
-    pub alpm_service: AlpmService,
+    pub alpm: AlpmWorker,
+    /// Where work spawned off the UI thread posts its results
+    sender: Sender<Message>,
     pub running_state: RunningState,
+    /// Navigation stack with the current screen last. Never empty, as the main screen
+    /// cannot be popped.
+    pub screens: Vec<Screen>,
+    /// Whether the screen needs to be drawn again
+    pub redraw: bool,
+    /// Counts ticks to animate progress indicators
+    pub ticks: usize,
     pub logs: LogDB,
+    /// Set until the logs have been read in the background
+    pub loading: bool,
+    pub load_error: Option<String>,
+    pub filter: Filter,
+    /// Whether keys are going to the filter prompt
+    pub filter_editing: bool,
+    /// Whether the next key picks an export format
+    pub export_prompt: bool,
+    /// The date being typed to mark as one end of a comparison, while keys go to its
+    /// prompt
+    pub date_prompt: Option<String>,
+    /// A short message about the outcome of the last action
+    pub notice: Option<String>,
+    /// Keys of the events shown in the transaction list
+    pub visible: Vec<LogKey>,
+    /// Positions in `visible` of the events matching the filter, empty without one
+    pub hits: Vec<usize>,
     pub list_state: ListState,
+    /// Dependencies looked up for the package screens
+    pub dependencies: DependencyCache,
+    requested_dependencies: HashSet<String>,
+    /// Start and end of the transaction or moment marked as one end of a comparison
+    pub compare_mark: Option<(LogKey, LogKey)>,
+    /// Set when following the log for new lines
+    pub following: bool,
+    /// Why following the log stopped
+    pub follow_error: Option<String>,
+    /// Package lookups for the focused package and the events around the selected one
+    pub package_info: PackageInfoCache,
     selected_event: usize,
 }
 
 impl Model {
-    pub fn new(alpm_service: AlpmService, log: &str) -> Result<Self, ParseError> {
-        let logs = LogDB::new(log)?;
-        let mut list_state = ListState::default();
-        let initial_event = logs.events.len().saturating_sub(1);
-        // List state needs be initialized with a length in order to select an item on
-        // initial render
-        list_state.select(Some(initial_event));
-        Ok(Self {
+    /// An empty model, to be filled once the logs have been loaded
+    pub fn new(alpm: AlpmWorker, sender: Sender<Message>) -> Self {
+        Self {
+            alpm,
+            sender,
             running_state: RunningState::Running,
-            alpm_service,
-            logs,
-            list_state,
-            selected_event: initial_event,
-        })
+            screens: vec![Screen::Main],
+            redraw: true,
+            ticks: 0,
+            logs: LogDB::default(),
+            loading: true,
+            load_error: None,
+            filter: Filter::default(),
+            filter_editing: false,
+            export_prompt: false,
+            date_prompt: None,
+            notice: None,
+            visible: Vec::new(),
+            hits: Vec::new(),
+            list_state: ListState::default(),
+            dependencies: DependencyCache::new(),
+            requested_dependencies: HashSet::new(),
+            compare_mark: None,
+            following: false,
+            follow_error: None,
+            package_info: PackageInfoCache::default(),
+            selected_event: 0,
+        }
+    }
+
+    /// Shows freshly loaded logs, starting at the most recent event
+    pub fn set_logs(&mut self, logs: LogDB) {
+        self.logs = logs;
+        self.loading = false;
+        self.update_visible();
+    }
+
+    pub fn screen(&self) -> &Screen {
+        self.screens.last().unwrap_or(&Screen::Main)
+    }
+
+    pub fn push_screen(&mut self, screen: Screen) {
+        self.screens.push(screen);
+    }
+
+    /// Returns to the previous screen, staying on the main screen
+    pub fn pop_screen(&mut self) {
+        if self.screens.len() > 1 {
+            self.screens.pop();
+        }
+    }
+
+    /// The package the current screen is about, or else the selected event's package
+    pub fn focused_package(&self) -> Option<&str> {
+        for screen in self.screens.iter().rev() {
+            match screen {
+                Screen::Package { package, .. } | Screen::History { package, .. } => {
+                    return Some(package);
+                }
+                Screen::Packages {
+                    view,
+                    packages,
+                    state,
+                    ..
+                } => {
+                    return packages
+                        .iter()
+                        .flatten()
+                        .filter(|package| view.includes(package))
+                        .nth(state.selected()?)
+                        .map(|package| package.name.as_str());
+                }
+                Screen::Main => break,
+                _ => {}
+            }
+        }
+        self.selected_event().and_then(|(_, event)| event.package())
+    }
+
+    /// Asks alpm about the focused package, and on the main screen about the packages of
+    /// the events around the selected one, unless that was already done
+    pub fn request_package_info(&mut self) {
+        let mut wanted: Vec<String> = self
+            .focused_package()
+            .map(String::from)
+            .into_iter()
+            .collect();
+        if matches!(self.screen(), Screen::Main) {
+            let start = self.selected_event.saturating_sub(PREFETCH);
+            let end = (self.selected_event + PREFETCH + 1).min(self.event_count());
+            wanted.extend(
+                self.visible[start.min(end)..end]
+                    .iter()
+                    .filter_map(|key| self.logs.events.get(key)?.package())
+                    .map(String::from),
+            );
+        }
+        for package in wanted {
+            if self.package_info.request(&package) {
+                self.alpm.package_info(&package);
+            }
+        }
+    }
+
+    pub fn set_package_info(&mut self, package: String, info: Option<Box<PackageInfo>>) {
+        self.package_info.insert(package, info.map(|info| *info));
+    }
+
+    /// Forgets lookups that pacman writing the databases may have made stale, so they
+    /// are asked again of the reopened handle
+    pub fn databases_changed(&mut self, change: DbChange) {
+        self.package_info.invalidate(change);
+        self.dependencies.clear();
+        self.requested_dependencies.clear();
+        if self
+            .screens
+            .iter()
+            .any(|screen| matches!(screen, Screen::Packages { .. }))
+        {
+            self.alpm.local_packages();
+        }
     }
 
     pub fn event_count(&self) -> usize {
-        self.logs.events.len()
+        self.visible.len()
     }
 
     pub fn select_previous(&mut self) {
@@ -50,6 +303,373 @@ impl Model {
         self.update_selected_event();
     }
 
+    /// Moves to the next match, wrapping around at the end of the list
+    pub fn select_next_match(&mut self) {
+        let next = self
+            .hits
+            .iter()
+            .find(|&&hit| hit > self.selected_event)
+            .or(self.hits.first());
+        if let Some(&index) = next {
+            self.select(index);
+        }
+    }
+
+    /// Moves to the previous match, wrapping around at the start of the list
+    pub fn select_previous_match(&mut self) {
+        let previous = self
+            .hits
+            .iter()
+            .rev()
+            .find(|&&hit| hit < self.selected_event)
+            .or(self.hits.last());
+        if let Some(&index) = previous {
+            self.select(index);
+        }
+    }
+
+    fn select(&mut self, index: usize) {
+        self.list_state.select(Some(index));
+        self.selected_event = index;
+    }
+
+    pub fn set_filter(&mut self, query: &str) {
+        self.filter = Filter::new(query);
+        self.update_visible();
+    }
+
+    /// Adds lines appended to the log while following it. The list keeps tailing the
+    /// log if the newest event was selected.
+    pub fn append_logs(&mut self, tail: LogDB) {
+        self.logs.merge(tail);
+        for screen in &mut self.screens {
+            if let Screen::Packages { installations, .. } = screen {
+                *installations = installed::installations(&self.logs);
+            }
+        }
+        let at_end = self.selected_event + 1 >= self.event_count();
+        self.update_visible();
+        if at_end {
+            self.select(self.event_count().saturating_sub(1));
+        }
+    }
+
+    /// Has the visible events written to a new file in the current directory, returning
+    /// its name
+    pub fn export_visible(&self, format: ExportFormat) -> PathBuf {
+        let now = OffsetDateTime::now_utc().unix_timestamp();
+        let path = PathBuf::from(format!(
+            "puckrat-{}.{}",
+            file_timestamp(now),
+            format.extension()
+        ));
+        worker::spawn_export(
+            self.sender.clone(),
+            self.logs.excerpt(&self.visible),
+            format,
+            path.clone(),
+        );
+        path
+    }
+
+    /// Shows every event for the focused package, starting at the most recent one like the
+    /// transaction list
+    pub fn show_history(&mut self) {
+        let Some(package) = self.focused_package().map(|package| package.to_string()) else {
+            return;
+        };
+        let last = self.logs.package_history(&package).len().saturating_sub(1);
+        self.push_screen(Screen::History {
+            package,
+            state: ListState::default().with_selected(Some(last)),
+        });
+    }
+
+    pub fn show_package(&mut self) {
+        if let Some(package) = self.focused_package().map(|package| package.to_string()) {
+            self.push_screen(Screen::package(package));
+        }
+    }
+
+    /// Asks alpm about the dependencies in the tree of the current package screen that have
+    /// not been looked up yet
+    pub fn request_dependencies(&mut self) {
+        let Some(Screen::Package {
+            package, expanded, ..
+        }) = self.package_screen()
+        else {
+            return;
+        };
+        let wanted: Vec<String> = deptree::rows(package, &self.dependencies, expanded)
+            .into_iter()
+            .filter_map(|row| match row {
+                Row::Loading { package, .. } => Some(package),
+                _ => None,
+            })
+            .collect();
+        for package in wanted {
+            if self.requested_dependencies.insert(package.clone()) {
+                self.alpm.dependencies(&package);
+            }
+        }
+    }
+
+    pub fn set_dependencies(&mut self, package: String, dependencies: Option<Box<Dependencies>>) {
+        if self.requested_dependencies.contains(&package) {
+            self.dependencies
+                .insert(package, dependencies.map(|dependencies| *dependencies));
+        }
+    }
+
+    /// Expands or collapses the selected node of the dependency tree
+    pub fn toggle_dependency(&mut self) {
+        let Some(Screen::Package {
+            package,
+            expanded,
+            state,
+            ..
+        }) = self.screens.last_mut()
+        else {
+            return;
+        };
+        let rows = deptree::rows(package, &self.dependencies, expanded);
+        if let Some(Row::Node {
+            path,
+            expandable: true,
+            ..
+        }) = state
+            .selected()
+            .and_then(|index| rows.into_iter().nth(index))
+            && !expanded.remove(&path)
+        {
+            expanded.insert(path);
+        }
+    }
+
+    /// Opens the package screen for the selected node of the dependency tree
+    pub fn open_dependency(&mut self) {
+        let Some(Screen::Package {
+            package,
+            expanded,
+            state,
+            ..
+        }) = self.screens.last()
+        else {
+            return;
+        };
+        let rows = deptree::rows(package, &self.dependencies, expanded);
+        if let Some(package) = state
+            .selected()
+            .and_then(|index| rows.get(index))
+            .and_then(|row| row.package())
+        {
+            let package = package.to_string();
+            self.push_screen(Screen::package(package));
+        }
+    }
+
+    /// Moves the focus of the package screen to its other pane
+    pub fn switch_package_pane(&mut self) {
+        if let Some(Screen::Package { focus, .. }) = self.screens.last_mut() {
+            *focus = match focus {
+                PackagePane::Details => PackagePane::Dependencies,
+                PackagePane::Dependencies => PackagePane::Details,
+            };
+        }
+    }
+
+    // The package screen on top, or under the help popup
+    fn package_screen(&self) -> Option<&Screen> {
+        self.screens
+            .iter()
+            .rev()
+            .find(|screen| !matches!(screen, Screen::Help))
+            .filter(|screen| matches!(screen, Screen::Package { .. }))
+    }
+
+    /// Plans a rollback of the selected event's transaction and has the cache searched in
+    /// the background
+    pub fn plan_rollback(&mut self) {
+        let Some(tx) = self
+            .selected_event()
+            .and_then(|(key, _)| self.logs.transaction_for(key))
+        else {
+            return;
+        };
+        let plan = RollbackPlan::new(&self.logs, tx);
+        self.push_screen(Screen::Rollback {
+            transaction: plan.transaction.clone(),
+            plan: None,
+            state: ListState::default().with_selected(Some(0)),
+        });
+        self.alpm.locate_rollback(plan);
+    }
+
+    /// Hands a located plan to the screens waiting for it
+    pub fn set_rollback(&mut self, located: RollbackPlan) {
+        for screen in &mut self.screens {
+            if let Screen::Rollback {
+                transaction, plan, ..
+            } = screen
+                && *transaction == located.transaction
+                && plan.is_none()
+            {
+                *plan = Some(located);
+                return;
+            }
+        }
+    }
+
+    /// Marks the selected event's transaction as one end of a comparison
+    pub fn mark_compare(&mut self) {
+        let Some(tx) = self
+            .selected_event()
+            .and_then(|(key, _)| self.logs.transaction_for(key))
+        else {
+            return;
+        };
+        let mark = (compare::before(tx), compare::after(&self.logs, tx));
+        self.mark(
+            mark,
+            format!("transaction at {}", render_timestamp(tx.start)),
+        );
+    }
+
+    /// Marks the date typed at the prompt as one end of a comparison
+    pub fn mark_date(&mut self) {
+        let Some(date) = self.date_prompt.take() else {
+            return;
+        };
+        match filter::parse_time(date.trim()) {
+            Ok(time) => self.mark(
+                (compare::at(time), compare::at(time)),
+                render_timestamp(time),
+            ),
+            Err(error) => self.notice = Some(error),
+        }
+    }
+
+    /// Once two points are marked, compares what was installed at the start of the earlier
+    /// one with what was installed at the end of the later one
+    fn mark(&mut self, (start, end): (LogKey, LogKey), description: String) {
+        let (from, to) = match self.compare_mark.take() {
+            Some(marked) if marked.0 != start => {
+                if marked.0 < start {
+                    (marked.0, end)
+                } else {
+                    (start, marked.1)
+                }
+            }
+            _ => {
+                self.notice = Some(format!(
+                    "Marked {}, mark a transaction with <m> or a date with <M> to compare",
+                    description
+                ));
+                self.compare_mark = Some((start, end));
+                return;
+            }
+        };
+        let comparison = Comparison::new(&self.logs, from, to);
+        self.alpm.size_comparison(comparison.clone());
+        self.push_screen(Screen::Compare {
+            comparison,
+            state: ListState::default().with_selected(Some(0)),
+        });
+    }
+
+    /// Hands the sizes looked up for a comparison to the screens showing it
+    pub fn set_comparison(&mut self, sized: Comparison) {
+        for screen in &mut self.screens {
+            if let Screen::Compare { comparison, .. } = screen
+                && comparison.from == sized.from
+                && comparison.to == sized.to
+                && comparison.size_deltas.is_none()
+            {
+                comparison.size_deltas = sized.size_deltas;
+                return;
+            }
+        }
+    }
+
+    /// Opens the installed packages and has the local database read in the background
+    pub fn show_packages(&mut self) {
+        self.push_screen(Screen::Packages {
+            view: PackageView::default(),
+            packages: None,
+            installations: installed::installations(&self.logs),
+            state: ListState::default().with_selected(Some(0)),
+        });
+        self.alpm.local_packages();
+    }
+
+    pub fn set_local_packages(&mut self, local: Vec<InstalledPackage>) {
+        for screen in &mut self.screens {
+            if let Screen::Packages { packages, .. } = screen {
+                *packages = Some(local.clone());
+            }
+        }
+    }
+
+    /// Shows the next group of installed packages on the current screen
+    pub fn switch_package_view(&mut self) {
+        if let Some(Screen::Packages { view, state, .. }) = self.screens.last_mut() {
+            *view = view.next();
+            state.select(Some(0));
+        }
+    }
+
+    /// Writes the rollback script of the current screen to a new file in the current
+    /// directory. It is not made executable, so that it gets read before it is run.
+    pub fn write_rollback_script(&self) -> io::Result<Option<PathBuf>> {
+        let Screen::Rollback {
+            plan: Some(plan), ..
+        } = self.screen()
+        else {
+            return Ok(None);
+        };
+        let path = PathBuf::from(format!(
+            "puckrat-rollback-{}.sh",
+            file_timestamp(plan.transaction.timestamp)
+        ));
+        File::create_new(&path)?.write_all(plan.script().as_bytes())?;
+        Ok(Some(path))
+    }
+
+    fn update_visible(&mut self) {
+        let selected = self.visible.get(self.selected_event).cloned();
+        self.visible.clear();
+        self.hits.clear();
+        if self.filter.query.trim().is_empty() {
+            self.visible.extend(self.logs.events.keys().cloned());
+        } else {
+            // Matches are shown with the rest of their transaction around them. Events
+            // before the first transaction stand alone.
+            let logs = &self.logs;
+            let group = |key: &LogKey| logs.transaction_for(key).map_or(key, |tx| &tx.key).clone();
+            let groups: BTreeSet<LogKey> = logs
+                .events
+                .iter()
+                .filter(|(key, event)| self.filter.matches(key, event))
+                .map(|(key, _)| group(key))
+                .collect();
+            for (key, event) in &logs.events {
+                if !groups.contains(&group(key)) {
+                    continue;
+                }
+                if self.filter.matches(key, event) {
+                    self.hits.push(self.visible.len());
+                }
+                self.visible.push(key.clone());
+            }
+        }
+        // Stay on the same event if it is still visible, else go to the last match
+        let index = selected
+            .and_then(|key| self.visible.binary_search(&key).ok())
+            .or(self.hits.last().copied())
+            .unwrap_or(self.visible.len().saturating_sub(1));
+        self.select(index);
+    }
+
     fn update_selected_event(&mut self) {
         if let Some(selected) = self.list_state.selected()
             && selected < self.event_count()
@@ -59,6 +679,16 @@ impl Model {
     }
 
     pub fn selected_event(&self) -> Option<(&LogKey, &LogEvent)> {
-        self.logs.events.iter().nth(self.selected_event)
+        let key = self.visible.get(self.selected_event)?;
+        self.logs.events.get_key_value(key)
     }
 }
+
+/// A timestamp that sorts and is safe to use in file names
+fn file_timestamp(timestamp: i64) -> String {
+    let format_description = format_description!("[year][month][day]-[hour][minute][second]");
+    OffsetDateTime::from_unix_timestamp(timestamp)
+        .ok()
+        .and_then(|time| time.format(&format_description).ok())
+        .unwrap_or_default()
+}
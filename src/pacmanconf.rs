@@ -0,0 +1,393 @@
+//! A reader for pacman.conf, following the format described in pacman.conf(5)
+use std::path::{Path, PathBuf};
+
+use alpm::SigLevel;
+use log::{debug, warn};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("Failed to read {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("{path}:{line}: {message}")]
+    Syntax {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct PacmanConfig {
+    pub root_dir: Option<PathBuf>,
+    pub db_path: Option<PathBuf>,
+    pub log_file: Option<PathBuf>,
+    pub cache_dirs: Vec<PathBuf>,
+    pub architectures: Vec<String>,
+    pub sig_level: Option<SigLevel>,
+    /// Repositories in the order they appear, which is also their priority
+    pub repos: Vec<Repo>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Repo {
+    pub name: String,
+    pub servers: Vec<String>,
+    pub sig_level: Option<SigLevel>,
+}
+
+impl PacmanConfig {
+    /// Reads pacman.conf at `path`. Paths of included files are passed through `resolve`
+    /// so they can be redirected into a sysroot.
+    pub fn load(path: &Path, resolve: &dyn Fn(&Path) -> PathBuf) -> Result<Self, ConfigError> {
+        let mut config = PacmanConfig::default();
+        let mut section = None;
+        config.read(path, &mut section, resolve)?;
+
+        // Servers are expanded last as Architecture may be set after the repos
+        let arch = config
+            .architectures
+            .first()
+            .cloned()
+            .unwrap_or_else(|| std::env::consts::ARCH.to_string());
+        for repo in &mut config.repos {
+            for server in &mut repo.servers {
+                *server = server.replace("$repo", &repo.name).replace("$arch", &arch);
+            }
+        }
+        debug!(
+            "Read {} repositories from {}",
+            config.repos.len(),
+            path.display()
+        );
+        Ok(config)
+    }
+
+    /// Like `load`, but falls back to pacman's defaults when there is no config to read,
+    /// as with logs copied from another machine or a container image. A config that
+    /// exists but does not parse is still an error.
+    pub fn load_or_default(
+        path: &Path,
+        resolve: &dyn Fn(&Path) -> PathBuf,
+    ) -> Result<Self, ConfigError> {
+        match Self::load(path, resolve) {
+            Err(ConfigError::Io {
+                path: unreadable,
+                source,
+            }) if unreadable == path => {
+                warn!(
+                    "Failed to read {}, using the defaults: {}",
+                    path.display(),
+                    source
+                );
+                Ok(PacmanConfig::default())
+            }
+            result => result,
+        }
+    }
+
+    fn read(
+        &mut self,
+        path: &Path,
+        section: &mut Option<String>,
+        resolve: &dyn Fn(&Path) -> PathBuf,
+    ) -> Result<(), ConfigError> {
+        let content = std::fs::read_to_string(path).map_err(|source| ConfigError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        let syntax_error = |line: usize, message: &str| ConfigError::Syntax {
+            path: path.to_path_buf(),
+            line: line + 1,
+            message: message.to_string(),
+        };
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .filter(|name| !name.is_empty())
+                    .ok_or_else(|| syntax_error(index, "invalid section header"))?;
+                if name != "options" {
+                    self.repos.push(Repo {
+                        name: name.to_string(),
+                        servers: Vec::new(),
+                        sig_level: None,
+                    });
+                }
+                *section = Some(name.to_string());
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim(), value.trim()),
+                None => (line, ""),
+            };
+            if section.is_none() {
+                return Err(syntax_error(index, "directive outside of a section"));
+            }
+
+            if key == "Include" {
+                for include in expand_include(&resolve(Path::new(value)))? {
+                    self.read(&include, section, resolve)?;
+                }
+            } else if section.as_deref() == Some("options") {
+                match key {
+                    "RootDir" => self.root_dir = Some(PathBuf::from(value)),
+                    "DBPath" => self.db_path = Some(PathBuf::from(value)),
+                    "LogFile" => self.log_file = Some(PathBuf::from(value)),
+                    "CacheDir" => self
+                        .cache_dirs
+                        .extend(value.split_whitespace().map(PathBuf::from)),
+                    "Architecture" => self
+                        .architectures
+                        .extend(value.split_whitespace().map(architecture)),
+                    "SigLevel" => {
+                        self.sig_level = Some(parse_sig_level(default_sig_level(), value))
+                    }
+                    _ => {}
+                }
+            } else if let Some(repo) = self.repos.last_mut() {
+                match key {
+                    "Server" => repo.servers.push(value.to_string()),
+                    "SigLevel" => {
+                        let base = self.sig_level.unwrap_or_else(default_sig_level);
+                        repo.sig_level = Some(parse_sig_level(base, value));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn architecture(arch: &str) -> String {
+    if arch == "auto" {
+        std::env::consts::ARCH.to_string()
+    } else {
+        arch.to_string()
+    }
+}
+
+// Include accepts a glob in the file name, e.g. /etc/pacman.d/*.conf
+fn expand_include(path: &Path) -> Result<Vec<PathBuf>, ConfigError> {
+    let Some(pattern) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(vec![path.to_path_buf()]);
+    };
+    if !pattern.contains('*') {
+        return Ok(vec![path.to_path_buf()]);
+    }
+    let dir = path.parent().unwrap_or(Path::new("/"));
+    let entries = std::fs::read_dir(dir).map_err(|source| ConfigError::Io {
+        path: dir.to_path_buf(),
+        source,
+    })?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_str()
+                .is_some_and(|name| wildcard_match(pattern, name))
+        })
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=name.len())
+                .filter(|&i| name.is_char_boundary(i))
+                .any(|i| wildcard_match(rest, &name[i..]))
+        }
+    }
+}
+
+// pacman's built in default: "Required DatabaseOptional"
+fn default_sig_level() -> SigLevel {
+    SigLevel::PACKAGE | SigLevel::DATABASE | SigLevel::DATABASE_OPTIONAL
+}
+
+// Mirrors process_siglevel() in pacman's conf.c
+fn parse_sig_level(base: SigLevel, value: &str) -> SigLevel {
+    let mut level = base;
+    for word in value.split_whitespace() {
+        let (word, package, database) = if let Some(word) = word.strip_prefix("Package") {
+            (word, true, false)
+        } else if let Some(word) = word.strip_prefix("Database") {
+            (word, false, true)
+        } else {
+            (word, true, true)
+        };
+        let mut apply = |package_bits: SigLevel, database_bits: SigLevel, set: bool| {
+            for (enabled, bits) in [(package, package_bits), (database, database_bits)] {
+                if !enabled {
+                    continue;
+                }
+                if set {
+                    level.insert(bits);
+                } else {
+                    level.remove(bits);
+                }
+            }
+        };
+        match word {
+            "Never" => apply(SigLevel::PACKAGE, SigLevel::DATABASE, false),
+            "Optional" => {
+                apply(SigLevel::PACKAGE, SigLevel::DATABASE, true);
+                apply(
+                    SigLevel::PACKAGE_OPTIONAL,
+                    SigLevel::DATABASE_OPTIONAL,
+                    true,
+                );
+            }
+            "Required" => {
+                apply(SigLevel::PACKAGE, SigLevel::DATABASE, true);
+                apply(
+                    SigLevel::PACKAGE_OPTIONAL,
+                    SigLevel::DATABASE_OPTIONAL,
+                    false,
+                );
+            }
+            "TrustedOnly" => {
+                apply(
+                    SigLevel::PACKAGE_MARGINAL_OK | SigLevel::PACKAGE_UNKNOWN_OK,
+                    SigLevel::DATABASE_MARGINAL_OK | SigLevel::DATABASE_UNKNOWN_OK,
+                    false,
+                );
+            }
+            "TrustAll" => {
+                apply(
+                    SigLevel::PACKAGE_MARGINAL_OK | SigLevel::PACKAGE_UNKNOWN_OK,
+                    SigLevel::DATABASE_MARGINAL_OK | SigLevel::DATABASE_UNKNOWN_OK,
+                    true,
+                );
+            }
+            _ => debug!("Ignoring unknown SigLevel {}", word),
+        }
+    }
+    level
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_dir;
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.conf", "custom.conf"));
+        assert!(wildcard_match("mirror*", "mirrorlist"));
+        assert!(!wildcard_match("*.conf", "custom.conf.pacnew"));
+    }
+
+    #[test]
+    fn test_parse_sig_level() {
+        assert_eq!(
+            parse_sig_level(default_sig_level(), "Never"),
+            SigLevel::DATABASE_OPTIONAL
+        );
+        assert_eq!(
+            parse_sig_level(SigLevel::empty(), "PackageRequired DatabaseOptional"),
+            SigLevel::PACKAGE | SigLevel::DATABASE | SigLevel::DATABASE_OPTIONAL
+        );
+    }
+
+    #[test]
+    fn test_load_or_default() {
+        let dir = temp_dir("conf-default");
+        let path = dir.join("pacman.conf");
+        let resolve = |p: &Path| p.to_path_buf();
+        let missing = PacmanConfig::load_or_default(&path, &resolve);
+
+        std::fs::write(&path, "DBPath = /srv/db/\n").unwrap();
+        let invalid = PacmanConfig::load_or_default(&path, &resolve);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(missing.unwrap(), PacmanConfig::default());
+        assert!(matches!(invalid, Err(ConfigError::Syntax { line: 1, .. })));
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = temp_dir("conf");
+        std::fs::write(
+            dir.join("mirrorlist"),
+            "Server = https://mirror.example.org/$repo/os/$arch\n",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("pacman.conf"),
+            format!(
+                "\
+[options]
+# comment
+DBPath = /srv/db/
+CacheDir = /srv/cache/ /var/cache/pacman/pkg/
+Architecture = x86_64 x86_64_v3
+CheckSpace
+
+[core]
+Include = {dir}/mirrorlist
+
+[custom]
+SigLevel = Optional TrustAll
+Server = file:///srv/repo # trailing comment
+",
+                dir = dir.display()
+            ),
+        )
+        .unwrap();
+
+        let config = PacmanConfig::load(&dir.join("pacman.conf"), &|p| p.to_path_buf()).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(config.db_path, Some(PathBuf::from("/srv/db/")));
+        assert_eq!(
+            config.cache_dirs,
+            vec![
+                PathBuf::from("/srv/cache/"),
+                PathBuf::from("/var/cache/pacman/pkg/")
+            ]
+        );
+        assert_eq!(config.architectures, vec!["x86_64", "x86_64_v3"]);
+        assert_eq!(
+            config.repos,
+            vec![
+                Repo {
+                    name: "core".to_string(),
+                    servers: vec!["https://mirror.example.org/core/os/x86_64".to_string()],
+                    sig_level: None,
+                },
+                Repo {
+                    name: "custom".to_string(),
+                    servers: vec!["file:///srv/repo".to_string()],
+                    sig_level: Some(
+                        default_sig_level()
+                            | SigLevel::PACKAGE_OPTIONAL
+                            | SigLevel::PACKAGE_MARGINAL_OK
+                            | SigLevel::PACKAGE_UNKNOWN_OK
+                            | SigLevel::DATABASE_MARGINAL_OK
+                            | SigLevel::DATABASE_UNKNOWN_OK
+                    ),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! Lookup of package files in pacman's package cache
+use std::path::PathBuf;
+
+use log::debug;
+
+#[derive(Debug, PartialEq)]
+pub struct CachedPackage {
+    pub path: PathBuf,
+    pub name: String,
+    pub version: String,
+}
+
+/// Finds every cached version of a package across all cache directories
+pub fn find_cached(cache_dirs: &[PathBuf], package_name: &str) -> Vec<CachedPackage> {
+    let mut packages = read_cache(cache_dirs, |file_name| file_name.starts_with(package_name));
+    packages.retain(|package| package.name == package_name);
+    packages
+}
+
+/// Lists every package file in all cache directories
+pub fn scan(cache_dirs: &[PathBuf]) -> Vec<CachedPackage> {
+    read_cache(cache_dirs, |_| true)
+}
+
+// `keep` skips files by name before they are parsed
+fn read_cache(cache_dirs: &[PathBuf], keep: impl Fn(&str) -> bool) -> Vec<CachedPackage> {
+    let mut packages = Vec::new();
+    for dir in cache_dirs {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                debug!("Could not read cache directory {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            if let Some(file_name) = entry.file_name().to_str()
+                && keep(file_name)
+                && let Some((name, version)) = parse_package_filename(file_name)
+            {
+                packages.push(CachedPackage {
+                    path: entry.path(),
+                    name: name.to_string(),
+                    version: version.to_string(),
+                });
+            }
+        }
+    }
+    packages
+}
+
+// Package files look like: gelly-4.1.0-1-x86_64.pkg.tar.zst, where the name may itself
+// contain dashes and the version is pkgver-pkgrel
+fn parse_package_filename(file_name: &str) -> Option<(&str, &str)> {
+    if file_name.ends_with(".sig") {
+        return None;
+    }
+    let stem = &file_name[..file_name.find(".pkg.tar")?];
+    let (name_version, _arch) = stem.rsplit_once('-')?;
+    let (name_pkgver, _pkgrel) = name_version.rsplit_once('-')?;
+    let (name, _pkgver) = name_pkgver.rsplit_once('-')?;
+    Some((name, &name_version[name.len() + 1..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_package_filename() {
+        assert_eq!(
+            parse_package_filename("gelly-4.1.0-1-x86_64.pkg.tar.zst"),
+            Some(("gelly", "4.1.0-1"))
+        );
+        assert_eq!(
+            parse_package_filename("lib32-gcc-libs-1:15.1.1+r7-1-x86_64.pkg.tar.xz"),
+            Some(("lib32-gcc-libs", "1:15.1.1+r7-1"))
+        );
+        assert_eq!(
+            parse_package_filename("gelly-4.1.0-1-x86_64.pkg.tar.zst.sig"),
+            None
+        );
+        assert_eq!(parse_package_filename("download-abc123"), None);
+    }
+}
@@ -0,0 +1,217 @@
+//! Undoing a transaction by installing the versions it replaced from the package cache
+//! and removing the packages it added. Nothing is ever executed; the result is a script
+//! to review and run by hand.
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use crate::format::render_timestamp;
+use crate::logparse::{LogDB, LogEvent, LogKey, Transaction};
+use crate::pkgcache::CachedPackage;
+
+#[derive(Debug)]
+pub enum RollbackStep {
+    /// Install a version from the package cache. `file` is `None` until it has been found
+    /// in the cache.
+    Install {
+        package: String,
+        version: String,
+        file: Option<PathBuf>,
+    },
+    /// Remove a package the transaction installed
+    Remove { package: String },
+}
+
+#[derive(Debug)]
+pub struct RollbackPlan {
+    /// Key of the transaction being rolled back, whose timestamp is its start
+    pub transaction: LogKey,
+    pub command: Option<String>,
+    pub steps: Vec<RollbackStep>,
+}
+
+impl RollbackPlan {
+    /// The inverse of every package change in a transaction. Reinstalls changed nothing
+    /// and are left out.
+    pub fn new(db: &LogDB, tx: &Transaction) -> Self {
+        let steps = tx
+            .packages
+            .iter()
+            .filter_map(|key| db.events.get(key))
+            .filter_map(|event| match event {
+                LogEvent::Upgraded {
+                    package,
+                    old_version,
+                    ..
+                }
+                | LogEvent::Downgraded {
+                    package,
+                    old_version,
+                    ..
+                }
+                | LogEvent::Removed {
+                    package,
+                    version: old_version,
+                } => Some(RollbackStep::Install {
+                    package: package.clone(),
+                    version: old_version.clone(),
+                    file: None,
+                }),
+                LogEvent::Installed { package, .. } => Some(RollbackStep::Remove {
+                    package: package.clone(),
+                }),
+                _ => None,
+            })
+            .collect();
+        RollbackPlan {
+            transaction: tx.key.clone(),
+            command: tx.command.clone(),
+            steps,
+        }
+    }
+
+    /// Finds the package files for the versions to install
+    pub fn locate(&mut self, cached: &[CachedPackage]) {
+        for step in &mut self.steps {
+            if let RollbackStep::Install {
+                package,
+                version,
+                file,
+            } = step
+            {
+                *file = cached
+                    .iter()
+                    .find(|cached| &cached.name == package && &cached.version == version)
+                    .map(|cached| cached.path.clone());
+            }
+        }
+    }
+
+    /// Versions to install that are not in the package cache
+    pub fn missing(&self) -> Vec<(&str, &str)> {
+        self.steps
+            .iter()
+            .filter_map(|step| match step {
+                RollbackStep::Install {
+                    package,
+                    version,
+                    file: None,
+                } => Some((package.as_str(), version.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// A shell script that rolls back with pacman. Packages missing from the cache are
+    /// listed in a comment and left out.
+    pub fn script(&self) -> String {
+        let mut script = String::from("#!/bin/sh\n");
+        let _ = writeln!(
+            script,
+            "# Rollback of the transaction started {}: {}",
+            render_timestamp(self.transaction.timestamp),
+            self.command.as_deref().unwrap_or("no command")
+        );
+        script.push_str(
+            "# Generated by puckrat. Review before running, nothing has been executed.\n",
+        );
+        let missing = self.missing();
+        if !missing.is_empty() {
+            script.push_str("#\n# Not in the package cache, so left out:\n");
+            for (package, version) in missing {
+                let _ = writeln!(script, "#   {} {}", package, version);
+            }
+        }
+        script.push_str("set -e\n");
+
+        let files: Vec<String> = self
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                RollbackStep::Install {
+                    file: Some(file), ..
+                } => Some(shell_quote(&file.to_string_lossy())),
+                _ => None,
+            })
+            .collect();
+        // Installing first keeps dependencies of the old versions satisfied while the
+        // new packages are removed
+        if !files.is_empty() {
+            let _ = writeln!(script, "pacman -U {}", files.join(" \\\n    "));
+        }
+        let removals: Vec<String> = self
+            .steps
+            .iter()
+            .filter_map(|step| match step {
+                RollbackStep::Remove { package } => Some(shell_quote(package)),
+                _ => None,
+            })
+            .collect();
+        if !removals.is_empty() {
+            let _ = writeln!(script, "pacman -R {}", removals.join(" "));
+        }
+        script
+    }
+}
+
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logparse::parse_fixture;
+
+    #[test]
+    fn test_rollback_plan() {
+        let content = "\
+[2026-02-02T08:00:00+0000] [PACMAN] Running 'pacman -Syu'
+[2026-02-02T08:00:10+0000] [ALPM] transaction started
+[2026-02-02T08:00:10+0000] [ALPM] upgraded gelly (4.0.6-10 -> 4.1.0-1)
+[2026-02-02T08:00:10+0000] [ALPM] downgraded linux (6.14.7.arch2-1 -> 6.14.6.arch1-1)
+[2026-02-02T08:00:10+0000] [ALPM] removed gelly-data (4.0.6-10)
+[2026-02-02T08:00:10+0000] [ALPM] installed gelly-assets (4.1.0-1)
+[2026-02-02T08:00:10+0000] [ALPM] reinstalled bash (5.3.3-2)
+[2026-02-02T08:00:11+0000] [ALPM] transaction completed
+";
+        let db = parse_fixture(content);
+        let tx = db.transactions.values().next().unwrap();
+        let mut plan = RollbackPlan::new(&db, tx);
+        assert_eq!(plan.steps.len(), 4);
+
+        let cached = |name: &str, version: &str| CachedPackage {
+            path: PathBuf::from(format!(
+                "/var/cache/pacman/pkg/{}-{}-x86_64.pkg.tar.zst",
+                name, version
+            )),
+            name: name.to_string(),
+            version: version.to_string(),
+        };
+        plan.locate(&[
+            cached("gelly", "4.0.6-10"),
+            cached("gelly", "4.1.0-1"),
+            cached("linux", "6.14.7.arch2-1"),
+        ]);
+        assert_eq!(plan.missing(), vec![("gelly-data", "4.0.6-10")]);
+        assert_eq!(
+            plan.script(),
+            "\
+#!/bin/sh
+# Rollback of the transaction started 2026-02-02 08:00:00: pacman -Syu
+# Generated by puckrat. Review before running, nothing has been executed.
+#
+# Not in the package cache, so left out:
+#   gelly-data 4.0.6-10
+set -e
+pacman -U '/var/cache/pacman/pkg/gelly-4.0.6-10-x86_64.pkg.tar.zst' \\
+    '/var/cache/pacman/pkg/linux-6.14.7.arch2-1-x86_64.pkg.tar.zst'
+pacman -R 'gelly-assets'
+"
+        );
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+}
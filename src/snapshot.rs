@@ -0,0 +1,242 @@
+//! The set of installed packages at a point in time, replayed from the log, and the
+//! differences between two such sets
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashSet};
+
+use crate::logparse::{LogDB, LogEvent, LogKey};
+
+/// Installed versions by package name
+pub type PackageSet = BTreeMap<String, String>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PackageDiff {
+    /// Installed in the newer set only
+    Added { package: String, version: String },
+    /// Installed in the older set only
+    Removed { package: String, version: String },
+    /// Installed in both, newer in the newer set. Versions in between are left out.
+    Upgraded {
+        package: String,
+        old_version: String,
+        new_version: String,
+    },
+    /// Installed in both, older in the newer set
+    Downgraded {
+        package: String,
+        old_version: String,
+        new_version: String,
+    },
+}
+
+impl PackageDiff {
+    pub fn package(&self) -> &str {
+        match self {
+            PackageDiff::Added { package, .. }
+            | PackageDiff::Removed { package, .. }
+            | PackageDiff::Upgraded { package, .. }
+            | PackageDiff::Downgraded { package, .. } => package,
+        }
+    }
+}
+
+/// Replays the events of every transaction that started by `timestamp`
+pub fn installed_at(db: &LogDB, timestamp: i64) -> PackageSet {
+    let bound = LogKey {
+        timestamp: timestamp.saturating_add(1),
+        offset: 0,
+    };
+    installed_before(db, &bound)
+}
+
+/// Replays the events with keys before `bound`.
+///
+/// The log may begin after packages were installed. A package whose first event after the
+/// bound is an upgrade, downgrade, reinstall or removal was installed before the log
+/// begins, and counts as installed at the version that event replaced.
+pub fn installed_before(db: &LogDB, bound: &LogKey) -> PackageSet {
+    let mut installed = PackageSet::new();
+    let mut seen = HashSet::new();
+    for (key, event) in &db.events {
+        let Some(package) = event.package() else {
+            continue;
+        };
+        if key < bound {
+            match event {
+                LogEvent::Installed { version, .. } | LogEvent::Reinstalled { version, .. } => {
+                    installed.insert(package.to_string(), version.clone());
+                }
+                LogEvent::Upgraded { new_version, .. }
+                | LogEvent::Downgraded { new_version, .. } => {
+                    installed.insert(package.to_string(), new_version.clone());
+                }
+                LogEvent::Removed { .. } => {
+                    installed.remove(package);
+                }
+                _ => {}
+            }
+            seen.insert(package);
+        } else if seen.insert(package) {
+            match event {
+                LogEvent::Upgraded { old_version, .. }
+                | LogEvent::Downgraded { old_version, .. }
+                | LogEvent::Reinstalled {
+                    version: old_version,
+                    ..
+                }
+                | LogEvent::Removed {
+                    version: old_version,
+                    ..
+                } => {
+                    installed.insert(package.to_string(), old_version.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+    installed
+}
+
+/// Adds the packages from `current` that never appear in the log. The log has no record
+/// of them changing, so they were installed all along.
+pub fn add_unlogged(installed: &mut PackageSet, db: &LogDB, current: &PackageSet) {
+    let logged: HashSet<&str> = db.events.values().filter_map(|e| e.package()).collect();
+    for (package, version) in current {
+        if !logged.contains(package.as_str()) {
+            installed
+                .entry(package.clone())
+                .or_insert_with(|| version.clone());
+        }
+    }
+}
+
+/// What changed going from `old` to `new`, by package name
+pub fn diff(old: &PackageSet, new: &PackageSet) -> Vec<PackageDiff> {
+    let mut changes = Vec::new();
+    for (package, old_version) in old {
+        match new.get(package) {
+            None => changes.push(PackageDiff::Removed {
+                package: package.clone(),
+                version: old_version.clone(),
+            }),
+            Some(new_version) => {
+                let (package, old_version, new_version) =
+                    (package.clone(), old_version.clone(), new_version.clone());
+                match alpm::vercmp(new_version.as_str(), old_version.as_str()) {
+                    Ordering::Greater => changes.push(PackageDiff::Upgraded {
+                        package,
+                        old_version,
+                        new_version,
+                    }),
+                    Ordering::Less => changes.push(PackageDiff::Downgraded {
+                        package,
+                        old_version,
+                        new_version,
+                    }),
+                    Ordering::Equal => {}
+                }
+            }
+        }
+    }
+    for (package, version) in new {
+        if !old.contains_key(package) {
+            changes.push(PackageDiff::Added {
+                package: package.clone(),
+                version: version.clone(),
+            });
+        }
+    }
+    changes.sort_by(|a, b| a.package().cmp(b.package()));
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::parse_date;
+    use crate::logparse::parse_fixture;
+
+    fn logs() -> LogDB {
+        let content = "\
+[2026-01-28T19:36:35+0000] [PACMAN] Running 'pacman -S gelly'
+[2026-01-28T19:36:50+0000] [ALPM] transaction started
+[2026-01-28T19:36:50+0000] [ALPM] installed gelly (4.0.6-10)
+[2026-01-28T19:36:51+0000] [ALPM] transaction completed
+[2026-02-02T08:00:00+0000] [PACMAN] Running 'pacman -Syu'
+[2026-02-02T08:00:10+0000] [ALPM] transaction started
+[2026-02-02T08:00:10+0000] [ALPM] upgraded gelly (4.0.6-10 -> 4.1.0-1)
+[2026-02-02T08:00:10+0000] [ALPM] upgraded linux (6.14.6.arch1-1 -> 6.14.7.arch2-1)
+[2026-02-02T08:00:10+0000] [ALPM] removed gelly-data (4.0.6-10)
+[2026-02-02T08:00:10+0000] [ALPM] installed gelly-assets (4.1.0-1)
+[2026-02-02T08:00:11+0000] [ALPM] transaction completed
+";
+        parse_fixture(content)
+    }
+
+    fn set(packages: &[(&str, &str)]) -> PackageSet {
+        packages
+            .iter()
+            .map(|(package, version)| (package.to_string(), version.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_installed_at() {
+        let db = logs();
+        assert_eq!(
+            installed_at(&db, parse_date("2026-02-01").unwrap()),
+            set(&[
+                ("gelly", "4.0.6-10"),
+                ("gelly-data", "4.0.6-10"),
+                ("linux", "6.14.6.arch1-1"),
+            ])
+        );
+        assert_eq!(
+            installed_at(&db, parse_date("2026-02-03").unwrap()),
+            set(&[
+                ("gelly", "4.1.0-1"),
+                ("gelly-assets", "4.1.0-1"),
+                ("linux", "6.14.7.arch2-1"),
+            ])
+        );
+        assert_eq!(
+            installed_at(&db, parse_date("2026-01-01").unwrap()),
+            set(&[("gelly-data", "4.0.6-10"), ("linux", "6.14.6.arch1-1")])
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let db = logs();
+        let current = set(&[
+            ("bash", "5.3.3-2"),
+            ("gelly", "4.1.0-1"),
+            ("gelly-assets", "4.1.0-1"),
+            ("linux", "6.14.5.arch1-1"),
+        ]);
+        let mut then = installed_at(&db, parse_date("2026-02-01").unwrap());
+        add_unlogged(&mut then, &db, &current);
+        assert_eq!(
+            diff(&then, &current),
+            vec![
+                PackageDiff::Upgraded {
+                    package: "gelly".to_string(),
+                    old_version: "4.0.6-10".to_string(),
+                    new_version: "4.1.0-1".to_string(),
+                },
+                PackageDiff::Added {
+                    package: "gelly-assets".to_string(),
+                    version: "4.1.0-1".to_string(),
+                },
+                PackageDiff::Removed {
+                    package: "gelly-data".to_string(),
+                    version: "4.0.6-10".to_string(),
+                },
+                PackageDiff::Downgraded {
+                    package: "linux".to_string(),
+                    old_version: "6.14.6.arch1-1".to_string(),
+                    new_version: "6.14.5.arch1-1".to_string(),
+                },
+            ]
+        );
+    }
+}
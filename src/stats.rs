@@ -0,0 +1,195 @@
+//! Statistics over the whole log for the stats screen
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use time::{Date, Month, OffsetDateTime};
+
+use crate::logparse::{LogDB, LogEvent, LogKey};
+
+const DAY: i64 = 24 * 60 * 60;
+const TOP: usize = 10;
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    /// Upgrades in each week from the first upgrade on, by the timestamp of its Monday
+    pub upgrades_per_week: Vec<(i64, u64)>,
+    /// Upgrades in each month from the first upgrade on
+    pub upgrades_per_month: Vec<((i32, Month), u64)>,
+    /// Packages by number of upgrades, most upgraded first
+    pub most_upgraded: Vec<(String, usize)>,
+    /// Transactions by number of package changes, largest first
+    pub largest_transactions: Vec<(LogKey, usize)>,
+    /// Start and end of the longest time between two full system upgrades
+    pub longest_sysupgrade_gap: Option<(i64, i64)>,
+    pub downgrades: usize,
+    /// Transactions that changed any packages
+    pub transactions: usize,
+    /// Package changes per transaction that changed any
+    pub average_transaction_size: f64,
+}
+
+impl Stats {
+    pub fn new(db: &LogDB) -> Self {
+        let mut weeks: BTreeMap<i64, u64> = BTreeMap::new();
+        let mut months: BTreeMap<(i32, Month), u64> = BTreeMap::new();
+        let mut upgrades: HashMap<&str, usize> = HashMap::new();
+        let mut downgrades = 0;
+        let mut previous_sysupgrade: Option<i64> = None;
+        let mut longest_sysupgrade_gap: Option<(i64, i64)> = None;
+        for (key, event) in &db.events {
+            match event {
+                LogEvent::Upgraded { package, .. } => {
+                    *weeks.entry(week_start(key.timestamp)).or_default() += 1;
+                    *months.entry(month(key.timestamp)).or_default() += 1;
+                    *upgrades.entry(package).or_default() += 1;
+                }
+                LogEvent::Downgraded { .. } => downgrades += 1,
+                LogEvent::FullSystemUpgrade => {
+                    if let Some(previous) = previous_sysupgrade
+                        && longest_sysupgrade_gap
+                            .is_none_or(|(start, end)| key.timestamp - previous > end - start)
+                    {
+                        longest_sysupgrade_gap = Some((previous, key.timestamp));
+                    }
+                    previous_sysupgrade = Some(key.timestamp);
+                }
+                _ => {}
+            }
+        }
+
+        let mut most_upgraded: Vec<(String, usize)> = upgrades
+            .into_iter()
+            .map(|(package, count)| (package.to_string(), count))
+            .collect();
+        most_upgraded.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        most_upgraded.truncate(TOP);
+
+        let mut largest_transactions: Vec<(LogKey, usize)> = db
+            .transactions
+            .values()
+            .filter(|tx| !tx.packages.is_empty())
+            .map(|tx| (tx.key.clone(), tx.packages.len()))
+            .collect();
+        let transactions = largest_transactions.len();
+        let changes: usize = largest_transactions.iter().map(|(_, count)| count).sum();
+        let average_transaction_size = if transactions > 0 {
+            changes as f64 / transactions as f64
+        } else {
+            0.0
+        };
+        // Stable, so equally large transactions stay oldest first
+        largest_transactions.sort_by_key(|(_, count)| Reverse(*count));
+        largest_transactions.truncate(TOP);
+
+        Stats {
+            upgrades_per_week: fill_weeks(weeks),
+            upgrades_per_month: fill_months(months),
+            most_upgraded,
+            largest_transactions,
+            longest_sysupgrade_gap,
+            downgrades,
+            transactions,
+            average_transaction_size,
+        }
+    }
+}
+
+/// Midnight of the Monday starting the week, counting from Monday 1969-12-29
+fn week_start(timestamp: i64) -> i64 {
+    let days = timestamp.div_euclid(DAY) + 3;
+    (days - days.rem_euclid(7) - 3) * DAY
+}
+
+fn month(timestamp: i64) -> (i32, Month) {
+    let date = OffsetDateTime::from_unix_timestamp(timestamp)
+        .map(|time| time.date())
+        .unwrap_or(Date::MIN);
+    (date.year(), date.month())
+}
+
+// Weeks and months without upgrades are missing from the counts but belong in a chart
+fn fill_weeks(weeks: BTreeMap<i64, u64>) -> Vec<(i64, u64)> {
+    let (Some(&first), Some(&last)) = (weeks.keys().next(), weeks.keys().next_back()) else {
+        return Vec::new();
+    };
+    (first..=last)
+        .step_by(7 * DAY as usize)
+        .map(|week| (week, weeks.get(&week).copied().unwrap_or(0)))
+        .collect()
+}
+
+fn fill_months(months: BTreeMap<(i32, Month), u64>) -> Vec<((i32, Month), u64)> {
+    let (Some(&first), Some(&last)) = (months.keys().next(), months.keys().next_back()) else {
+        return Vec::new();
+    };
+    let mut filled = Vec::new();
+    let mut current = first;
+    while current <= last {
+        filled.push((current, months.get(&current).copied().unwrap_or(0)));
+        let (year, month) = current;
+        current = if month == Month::December {
+            (year + 1, Month::January)
+        } else {
+            (year, month.next())
+        };
+    }
+    filled
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::filter::parse_date;
+    use crate::logparse::parse_fixture;
+
+    #[test]
+    fn test_stats() {
+        let content = "\
+[2026-01-05T10:00:00+0000] [PACMAN] Running 'pacman -Syu'
+[2026-01-05T10:00:01+0000] [PACMAN] starting full system upgrade
+[2026-01-05T10:00:10+0000] [ALPM] transaction started
+[2026-01-05T10:00:10+0000] [ALPM] upgraded gelly (4.0.6-10 -> 4.1.0-1)
+[2026-01-05T10:00:10+0000] [ALPM] upgraded linux (6.14.6.arch1-1 -> 6.14.7.arch2-1)
+[2026-01-05T10:00:11+0000] [ALPM] transaction completed
+[2026-01-20T10:00:00+0000] [PACMAN] Running 'pacman -U linux-6.14.6.arch1-1-x86_64.pkg.tar.zst'
+[2026-01-20T10:00:10+0000] [ALPM] transaction started
+[2026-01-20T10:00:10+0000] [ALPM] downgraded linux (6.14.7.arch2-1 -> 6.14.6.arch1-1)
+[2026-01-20T10:00:11+0000] [ALPM] transaction completed
+[2026-02-02T08:00:00+0000] [PACMAN] Running 'pacman -Syu'
+[2026-02-02T08:00:01+0000] [PACMAN] starting full system upgrade
+[2026-02-02T08:00:10+0000] [ALPM] transaction started
+[2026-02-02T08:00:10+0000] [ALPM] upgraded linux (6.14.6.arch1-1 -> 6.14.8.arch1-1)
+[2026-02-02T08:00:11+0000] [ALPM] transaction completed
+";
+        let db = parse_fixture(content);
+        let stats = Stats::new(&db);
+
+        let monday = |date| parse_date(date).unwrap();
+        assert_eq!(
+            stats.upgrades_per_week,
+            vec![
+                (monday("2026-01-05"), 2),
+                (monday("2026-01-12"), 0),
+                (monday("2026-01-19"), 0),
+                (monday("2026-01-26"), 0),
+                (monday("2026-02-02"), 1),
+            ]
+        );
+        assert_eq!(
+            stats.upgrades_per_month,
+            vec![((2026, Month::January), 2), ((2026, Month::February), 1)]
+        );
+        assert_eq!(
+            stats.most_upgraded,
+            vec![("linux".to_string(), 2), ("gelly".to_string(), 1)]
+        );
+        assert_eq!(stats.largest_transactions[0].1, 2);
+        assert_eq!(
+            stats.longest_sysupgrade_gap,
+            Some((monday("2026-01-05") + 36000, monday("2026-02-02") + 28800))
+        );
+        assert_eq!(stats.downgrades, 1);
+        assert_eq!(stats.transactions, 3);
+        assert!((stats.average_transaction_size - 4.0 / 3.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,11 @@
+//! Helpers shared by the tests of several modules
+use std::path::PathBuf;
+
+/// An empty directory of its own for the test `name`, which the test removes when done
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("puckrat-{}-{}", name, std::process::id()));
+    // Left behind by a failed run
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
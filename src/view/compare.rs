@@ -0,0 +1,134 @@
+use ratatui::{
+    Frame,
+    prelude::*,
+    symbols::border,
+    widgets::{Block, List, ListItem},
+};
+
+use crate::{
+    compare::Comparison,
+    format::{render_size, render_timestamp},
+    logparse::LogKey,
+    model::{Model, Screen},
+    snapshot::PackageDiff,
+    view::SELECTED_STYLE,
+};
+
+pub fn render(model: &mut Model, index: usize, frame: &mut Frame) {
+    let Screen::Compare { comparison, state } = &mut model.screens[index] else {
+        return;
+    };
+    let title = Line::from(
+        format!(
+            " Changes from {} to {} ",
+            render_point(&comparison.from),
+            render_point(&comparison.to)
+        )
+        .bold(),
+    );
+    let instructions = Line::from(vec![
+        " Down ".into(),
+        "<j>".blue().bold(),
+        " Up ".into(),
+        "<k>".blue().bold(),
+        " Back ".into(),
+        "<Esc> ".blue().bold(),
+    ]);
+    let block = Block::bordered()
+        .title(title.centered())
+        .title(render_summary(comparison).right_aligned())
+        .title_bottom(instructions.centered())
+        .border_set(border::THICK);
+
+    let items: Vec<ListItem> = if comparison.changes.is_empty() {
+        vec![ListItem::from("No packages changed".italic())]
+    } else {
+        comparison
+            .changes
+            .iter()
+            .enumerate()
+            .map(|(index, change)| {
+                let delta = comparison.size_deltas.as_ref().map(|deltas| deltas[index]);
+                render_change(change, delta)
+            })
+            .collect()
+    };
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(SELECTED_STYLE)
+        .highlight_symbol(">")
+        .highlight_spacing(ratatui::widgets::HighlightSpacing::Always);
+    frame.render_stateful_widget(list, frame.area(), state);
+}
+
+fn render_point(key: &LogKey) -> String {
+    if key.timestamp == i64::MAX {
+        "now".to_string()
+    } else {
+        render_timestamp(key.timestamp)
+    }
+}
+
+fn render_summary(comparison: &Comparison) -> Line<'static> {
+    let counts = comparison.counts();
+    let mut spans = vec![
+        format!(" {} added", counts.added).green(),
+        format!(", {} removed", counts.removed).red(),
+        format!(", {} upgraded", counts.upgraded).into(),
+        format!(", {} downgraded", counts.downgraded).yellow(),
+    ];
+    spans.push(match comparison.size_delta() {
+        None => ", sizes…".dim(),
+        Some((total, 0)) => format!(", {}", render_size_delta(total)).into(),
+        Some((total, unknown)) => {
+            format!(", {} ({} unknown)", render_size_delta(total), unknown).into()
+        }
+    });
+    spans.push(" ".into());
+    Line::from(spans)
+}
+
+fn render_change(change: &PackageDiff, delta: Option<Option<i64>>) -> ListItem<'static> {
+    let mut spans = match change {
+        PackageDiff::Added { package, version } => vec![
+            "added      ".green(),
+            package.clone().bold(),
+            format!(" {}", version).into(),
+        ],
+        PackageDiff::Removed { package, version } => vec![
+            "removed    ".red(),
+            package.clone().bold(),
+            format!(" {}", version).into(),
+        ],
+        PackageDiff::Upgraded {
+            package,
+            old_version,
+            new_version,
+        } => vec![
+            "upgraded   ".into(),
+            package.clone().bold(),
+            format!(" {} -> {}", old_version, new_version).into(),
+        ],
+        PackageDiff::Downgraded {
+            package,
+            old_version,
+            new_version,
+        } => vec![
+            "downgraded ".yellow(),
+            package.clone().bold(),
+            format!(" {} -> {}", old_version, new_version).into(),
+        ],
+    };
+    if let Some(Some(delta)) = delta {
+        spans.push(format!("  {}", render_size_delta(delta)).dim());
+    }
+    ListItem::from(Line::from(spans))
+}
+
+fn render_size_delta(bytes: i64) -> String {
+    if bytes > 0 {
+        format!("+{}", render_size(bytes))
+    } else {
+        render_size(bytes)
+    }
+}
@@ -0,0 +1,62 @@
+use ratatui::{
+    Frame,
+    prelude::*,
+    symbols::border,
+    widgets::{Block, List, ListItem},
+};
+
+use crate::{
+    model::{Model, Screen},
+    view::SELECTED_STYLE,
+};
+
+pub fn render(model: &mut Model, index: usize, frame: &mut Frame) {
+    let Screen::Diagnostics(state) = &mut model.screens[index] else {
+        return;
+    };
+    let title = Line::from(
+        format!(
+            " {} lines could not be parsed ",
+            model.logs.diagnostics.len()
+        )
+        .bold(),
+    );
+    let instructions = Line::from(vec![
+        " Down ".into(),
+        "<j>".blue().bold(),
+        " Up ".into(),
+        "<k>".blue().bold(),
+        " Back ".into(),
+        "<Esc> ".blue().bold(),
+    ]);
+    let block = Block::bordered()
+        .title(title.centered())
+        .title_bottom(instructions.centered())
+        .border_set(border::THICK);
+    let items: Vec<ListItem> = model
+        .logs
+        .diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let mut spans = match (&diagnostic.path, diagnostic.line) {
+                (Some(path), Some(line)) => vec![format!("{}:{} ", path.display(), line).bold()],
+                // A whole file that could not be read
+                (Some(path), None) => vec![format!("{}: ", path.display()).bold()],
+                (None, Some(line)) => vec![format!("Line {} ", line).bold()],
+                (None, None) => Vec::new(),
+            };
+            if diagnostic.line.is_some() {
+                spans.push(format!("(byte {}): ", diagnostic.offset).dim());
+            }
+            spans.push(diagnostic.error.to_string().red());
+            ListItem::from(vec![Line::from(spans), Line::from(diagnostic.text.clone())])
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(SELECTED_STYLE)
+        .highlight_symbol(">")
+        .highlight_spacing(ratatui::widgets::HighlightSpacing::Always);
+    frame.render_stateful_widget(list, frame.area(), state);
+}
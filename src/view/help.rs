@@ -0,0 +1,39 @@
+use ratatui::{
+    Frame,
+    prelude::*,
+    symbols::border,
+    widgets::{Block, Clear, Padding, Paragraph},
+};
+
+use crate::{
+    event::{GLOBAL_KEY_HELP, key_help},
+    model::Screen,
+};
+
+/// Lists the keys for `screen` in a popup over it
+pub fn render(screen: &Screen, frame: &mut Frame) {
+    let keys = key_help(screen);
+    let lines: Vec<Line> = keys
+        .iter()
+        .chain(GLOBAL_KEY_HELP)
+        .map(|(key, description)| {
+            Line::from(vec![
+                format!("{:>10}  ", key).blue().bold(),
+                description.to_string().into(),
+            ])
+        })
+        .collect();
+    let instructions = Line::from(vec![" Close ".into(), "<Esc> ".blue().bold()]);
+    let block = Block::bordered()
+        .title(Line::from(" Keys ".bold()).centered())
+        .title_bottom(instructions.centered())
+        .padding(Padding::horizontal(1))
+        .border_set(border::THICK);
+
+    let height = lines.len() as u16 + 2;
+    let area = frame
+        .area()
+        .centered(Constraint::Length(64), Constraint::Length(height));
+    frame.render_widget(Clear, area);
+    frame.render_widget(Paragraph::new(lines).block(block), area);
+}
@@ -0,0 +1,97 @@
+use ratatui::{
+    Frame,
+    prelude::*,
+    symbols::border,
+    widgets::{Block, List, ListItem},
+};
+
+use crate::{
+    format::render_timestamp,
+    logparse::{LogDB, LogEvent, LogKey},
+    model::{Model, Screen},
+    view::{SELECTED_STYLE, transactions::render_event_summary},
+};
+
+pub fn render(model: &mut Model, index: usize, frame: &mut Frame) {
+    let Screen::History { package, state } = &mut model.screens[index] else {
+        return;
+    };
+    let history = model.logs.package_history(package);
+    let title = Line::from(format!(" History of {} ", package).bold());
+    let instructions = Line::from(vec![
+        " Down ".into(),
+        "<j>".blue().bold(),
+        " Up ".into(),
+        "<k>".blue().bold(),
+        " Details ".into(),
+        "<i>".blue().bold(),
+        " Back ".into(),
+        "<Esc> ".blue().bold(),
+    ]);
+    let block = Block::bordered()
+        .title(title.centered())
+        .title(Line::from(format!(" {} events ", history.len()).dim()).right_aligned())
+        .title_bottom(instructions.centered())
+        .border_set(border::THICK);
+
+    let mut previous: Option<i64> = None;
+    let items: Vec<ListItem> = history
+        .into_iter()
+        .map(|(key, event)| {
+            let item = render_history_item(&model.logs, key, event, previous);
+            previous = Some(key.timestamp);
+            item
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(SELECTED_STYLE)
+        .highlight_symbol(">")
+        .highlight_spacing(ratatui::widgets::HighlightSpacing::Always);
+    frame.render_stateful_widget(list, frame.area(), state);
+}
+
+fn render_history_item(
+    db: &LogDB,
+    key: &LogKey,
+    event: &LogEvent,
+    previous: Option<i64>,
+) -> ListItem<'static> {
+    let mut header = vec![Span::from(render_timestamp(key.timestamp)).bold()];
+    if let Some(previous) = previous {
+        header.push(
+            format!(
+                "  {} after previous",
+                render_duration(key.timestamp - previous)
+            )
+            .dim(),
+        );
+    }
+    let transaction = match db.transaction_for(key) {
+        Some(tx) if tx.implicit => "implicit transaction".to_string(),
+        Some(tx) => match &tx.command {
+            Some(command) => format!("$ {}", command),
+            None => format!("transaction at {}", render_timestamp(tx.start)),
+        },
+        None => "no transaction".to_string(),
+    };
+    ListItem::from(vec![
+        Line::from(header),
+        Line::from(render_event_summary(event)),
+        Line::from(format!("  {}", transaction).dim()),
+    ])
+}
+
+fn render_duration(seconds: i64) -> String {
+    let minutes = seconds / 60;
+    let hours = minutes / 60;
+    let days = hours / 24;
+    if days > 0 {
+        format!("{}d {}h", days, hours % 24)
+    } else if hours > 0 {
+        format!("{}h {}m", hours, minutes % 60)
+    } else {
+        format!("{}m", minutes)
+    }
+}
@@ -2,7 +2,13 @@ use ratatui::{Frame, prelude::*};
 
 use crate::{
     model::Model,
-    view::{pkginfo::render_package, transactions::render_transactions},
+    view::{
+        pkginfo::render_package,
+        transactions::{
+            render_date_prompt, render_export_prompt, render_filter_bar, render_status,
+            render_transactions,
+        },
+    },
 };
 
 pub fn render(model: &mut Model, frame: &mut Frame) {
@@ -10,8 +16,41 @@ pub fn render(model: &mut Model, frame: &mut Frame) {
         .direction(Direction::Horizontal)
         .constraints(vec![Constraint::Percentage(40), Constraint::Percentage(60)])
         .split(frame.area());
-    let tx_widget = render_transactions(&model.logs);
-    frame.render_stateful_widget(tx_widget, layout[0], &mut model.list_state);
-    let pkg_widget = render_package(model);
+    let mut list_area = layout[0];
+    let bar = if model.export_prompt {
+        Some(render_export_prompt())
+    } else if let Some(date) = &model.date_prompt {
+        Some(render_date_prompt(date))
+    } else if model.filter_editing || !model.filter.query.is_empty() {
+        Some(render_filter_bar(model))
+    } else {
+        None
+    };
+    if let Some(bar) = bar {
+        let left = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(vec![Constraint::Min(0), Constraint::Length(3)])
+            .split(layout[0]);
+        list_area = left[0];
+        frame.render_widget(bar, left[1]);
+    }
+    let status = render_status(model);
+    let tx_widget = render_transactions(
+        &model.logs,
+        &model.visible,
+        &model.hits,
+        &model.filter,
+        status,
+    );
+    frame.render_stateful_widget(tx_widget, list_area, &mut model.list_state);
+    let instructions = Line::from(vec![
+        " Details ".into(),
+        "<i>".blue().bold(),
+        " Help ".into(),
+        "<?>".blue().bold(),
+        " Quit ".into(),
+        "<q> ".blue().bold(),
+    ]);
+    let pkg_widget = render_package(model, instructions);
     frame.render_widget(pkg_widget, layout[1]);
 }
@@ -1,12 +1,42 @@
+mod compare;
+mod diagnostics;
+mod help;
+mod history;
 mod main_screen;
+mod package;
+mod packages;
 mod pkginfo;
-mod transactions;
+mod rollback;
+mod stats;
+pub mod transactions;
 
-use crate::model::Model;
-use ratatui::Frame;
+use crate::model::{Model, Screen};
+use ratatui::{prelude::*, style::palette::tailwind::SLATE};
+
+/// Highlights the selected row of a list
+pub(crate) const SELECTED_STYLE: Style = Style::new().bg(SLATE.c600).add_modifier(Modifier::BOLD);
 
 pub fn view(model: &mut Model, frame: &mut Frame) {
-    // For now, just render main screen
-    // Future: match on model.current_screen or similar
-    main_screen::render(model, frame);
+    let top = model.screens.len().saturating_sub(1);
+    if matches!(model.screen(), Screen::Help) && top > 0 {
+        // Help pops up over the screen it is for
+        render_screen(model, top - 1, frame);
+        help::render(&model.screens[top - 1], frame);
+    } else {
+        render_screen(model, top, frame);
+    }
+}
+
+fn render_screen(model: &mut Model, index: usize, frame: &mut Frame) {
+    match model.screens[index] {
+        Screen::Main => main_screen::render(model, frame),
+        Screen::Package { .. } => package::render(model, index, frame),
+        Screen::Diagnostics(_) => diagnostics::render(model, index, frame),
+        Screen::History { .. } => history::render(model, index, frame),
+        Screen::Rollback { .. } => rollback::render(model, index, frame),
+        Screen::Compare { .. } => compare::render(model, index, frame),
+        Screen::Stats(_) => stats::render(model, index, frame),
+        Screen::Packages { .. } => packages::render(model, index, frame),
+        Screen::Help => {}
+    }
 }
@@ -0,0 +1,130 @@
+use ratatui::{
+    Frame,
+    prelude::*,
+    symbols::border,
+    widgets::{Block, List, ListItem, Paragraph, Wrap},
+};
+
+use crate::{
+    alpmutil::Resolution,
+    deptree::{self, Row},
+    model::{Model, PackagePane, Screen},
+    view::{
+        SELECTED_STYLE,
+        pkginfo::{package_block, package_text},
+    },
+};
+
+pub fn render(model: &mut Model, index: usize, frame: &mut Frame) {
+    let layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(frame.area());
+    let text = package_text(model);
+    let Screen::Package { focus, scroll, .. } = &mut model.screens[index] else {
+        return;
+    };
+    // Scrolling stops with the last line at the top
+    *scroll = (*scroll).min(text.height().saturating_sub(1) as u16);
+    let (focus, scroll) = (*focus, *scroll);
+    let instructions = Line::from(vec![
+        " Switch pane ".into(),
+        "<Tab>".blue().bold(),
+        " History ".into(),
+        "<h>".blue().bold(),
+        " Back ".into(),
+        "<Esc> ".blue().bold(),
+    ]);
+    let details = Paragraph::new(text)
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+        .block(package_block(
+            model,
+            instructions,
+            focus == PackagePane::Details,
+        ));
+    frame.render_widget(details, layout[0]);
+
+    let Screen::Package {
+        package,
+        expanded,
+        state,
+        ..
+    } = &mut model.screens[index]
+    else {
+        return;
+    };
+    let instructions = Line::from(vec![
+        " Expand ".into(),
+        "<Space>".blue().bold(),
+        " Open ".into(),
+        "<Enter> ".blue().bold(),
+    ]);
+    let border_set = if focus == PackagePane::Dependencies {
+        border::THICK
+    } else {
+        border::PLAIN
+    };
+    let block = Block::bordered()
+        .title(Line::from(" Dependencies ".bold()).centered())
+        .title_bottom(instructions.centered())
+        .border_set(border_set);
+    let rows = deptree::rows(package, &model.dependencies, expanded);
+    let items: Vec<ListItem> = if rows.is_empty() {
+        vec![ListItem::from("Not known to any database".italic())]
+    } else {
+        rows.iter().map(render_row).collect()
+    };
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(SELECTED_STYLE)
+        .highlight_symbol(">")
+        .highlight_spacing(ratatui::widgets::HighlightSpacing::Always);
+    frame.render_stateful_widget(list, layout[1], state);
+}
+
+fn render_row(row: &Row) -> ListItem<'static> {
+    match row {
+        Row::Section(section) => ListItem::from(Line::from(section.title().bold())),
+        Row::Node {
+            depth,
+            dependency,
+            expanded,
+            expandable,
+            ..
+        } => {
+            let marker = match (expandable, expanded) {
+                (false, _) => "  ",
+                (true, false) => "▸ ",
+                (true, true) => "▾ ",
+            };
+            let mut spans = vec![
+                indent(*depth).into(),
+                marker.into(),
+                dependency.spec.clone().into(),
+            ];
+            // Shown when the dependency is provided by a package of another name
+            if let Some(satisfier) = &dependency.satisfier
+                && !dependency.spec.starts_with(satisfier.as_str())
+            {
+                spans.push(format!(" by {}", satisfier).dim());
+            }
+            spans.push(match dependency.resolution {
+                Resolution::Installed => "  installed".green(),
+                Resolution::Available => "  available".yellow(),
+                Resolution::Missing => "  missing".red().bold(),
+            });
+            ListItem::from(Line::from(spans))
+        }
+        Row::Loading { depth, .. } => {
+            ListItem::from(Line::from(format!("{}  Looking up…", indent(*depth)).dim()))
+        }
+        Row::Empty { depth } => {
+            ListItem::from(Line::from(format!("{}  none", indent(*depth)).dim()))
+        }
+    }
+}
+
+fn indent(depth: usize) -> String {
+    "  ".repeat(depth)
+}
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+
+use ratatui::{
+    Frame,
+    prelude::*,
+    symbols::border,
+    widgets::{Block, List, ListItem},
+};
+
+use crate::{
+    format::render_timestamp,
+    installed::{InstalledPackage, PackageView},
+    logparse::{LogDB, LogKey},
+    model::{Model, Screen},
+    view::{SELECTED_STYLE, pkginfo::render_package},
+};
+
+pub fn render(model: &mut Model, index: usize, frame: &mut Frame) {
+    let layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(frame.area());
+    let instructions = Line::from(vec![
+        " Details ".into(),
+        "<i>".blue().bold(),
+        " History ".into(),
+        "<h>".blue().bold(),
+        " Back ".into(),
+        "<Esc> ".blue().bold(),
+    ]);
+    frame.render_widget(render_package(model, instructions), layout[1]);
+
+    let Screen::Packages {
+        view,
+        packages,
+        installations,
+        state,
+    } = &mut model.screens[index]
+    else {
+        return;
+    };
+    let instructions = Line::from(vec![" Switch ".into(), "<Tab> ".blue().bold()]);
+    let block = Block::bordered()
+        .title(render_tabs(*view, packages.as_deref()).centered())
+        .title_bottom(instructions.centered())
+        .border_set(border::THICK);
+    let items: Vec<ListItem> = match packages {
+        None => vec![ListItem::from("Reading the local database…".italic())],
+        Some(packages) => {
+            let items: Vec<ListItem> = packages
+                .iter()
+                .filter(|package| view.includes(package))
+                .map(|package| render_installed(&model.logs, installations, package))
+                .collect();
+            if items.is_empty() {
+                vec![ListItem::from("None".italic())]
+            } else {
+                items
+            }
+        }
+    };
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(SELECTED_STYLE)
+        .highlight_symbol(">")
+        .highlight_spacing(ratatui::widgets::HighlightSpacing::Always);
+    frame.render_stateful_widget(list, layout[0], state);
+}
+
+// Every view with how many packages it has, the current one highlighted
+fn render_tabs(current: PackageView, packages: Option<&[InstalledPackage]>) -> Line<'static> {
+    let mut spans = vec![" ".into()];
+    for view in PackageView::ALL {
+        let title = match packages {
+            Some(packages) => {
+                let count = packages
+                    .iter()
+                    .filter(|package| view.includes(package))
+                    .count();
+                format!(" {} ({}) ", view.title(), count)
+            }
+            None => format!(" {} ", view.title()),
+        };
+        spans.push(if view == current {
+            title.bold().reversed()
+        } else {
+            title.dim()
+        });
+    }
+    spans.push(" ".into());
+    Line::from(spans)
+}
+
+// Where the log says the package came from, as the reason it is there
+fn render_installed(
+    db: &LogDB,
+    installations: &HashMap<String, LogKey>,
+    package: &InstalledPackage,
+) -> ListItem<'static> {
+    let installed = match installations.get(&package.name) {
+        Some(key) => {
+            let command = db
+                .transaction_for(key)
+                .and_then(|tx| tx.command.as_deref())
+                .map(|command| format!(" by $ {}", command))
+                .unwrap_or_default();
+            format!("  installed {}{}", render_timestamp(key.timestamp), command)
+        }
+        None => "  installed before the log begins".to_string(),
+    };
+    ListItem::from(vec![
+        Line::from(vec![
+            package.name.clone().bold(),
+            " ".into(),
+            package.version.clone().dim(),
+        ]),
+        Line::from(installed.dim()),
+    ])
+}
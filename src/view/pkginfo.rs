@@ -1,4 +1,11 @@
-use crate::{alpmutil::PackageInfo, model::Model};
+use alpm::{PackageReason, PackageValidation};
+
+use crate::{
+    alpmutil::PackageInfo,
+    format::{render_size, render_timestamp},
+    logparse::PackageSummary,
+    model::Model,
+};
 use ratatui::{
     prelude::*,
     symbols::border,
@@ -6,36 +13,134 @@ use ratatui::{
     widgets::{Block, Padding, Paragraph, Wrap},
 };
 
-pub fn render_package(model: &Model) -> Paragraph<'_> {
-    let event = model.selected_event();
-    let package_name = if let Some(event) = event {
-        event.1.package()
-    } else {
-        "Puckman"
-    };
+/// Shows the focused package, or which log the selected event came from when it is about
+/// the same package
+pub fn render_package<'a>(model: &'a Model, instructions: Line<'a>) -> Paragraph<'a> {
+    Paragraph::new(package_text(model))
+        .wrap(Wrap { trim: false })
+        .block(package_block(model, instructions, true))
+}
+
+/// The border of the package pane, thick when the pane has the focus
+pub fn package_block<'a>(model: &'a Model, instructions: Line<'a>, focused: bool) -> Block<'a> {
+    let focused_package = model.focused_package();
+    let package_name = focused_package.unwrap_or("Puckman");
     let title = Line::from(format!(" {} ", package_name).bold());
-    let instructions = Line::from(vec![" Quit ".into(), "<Q> ".blue().bold()]);
-    let block = Block::bordered()
+    let mut block = Block::bordered()
         .title(title.centered())
         .title_bottom(instructions.centered())
         .padding(Padding::new(1, 1, 1, 1))
-        .border_set(border::THICK);
+        .border_set(if focused {
+            border::THICK
+        } else {
+            border::PLAIN
+        });
+    let selected = model
+        .selected_event()
+        .filter(|(_, event)| focused_package.is_some() && event.package() == focused_package);
+    if let Some(source) = selected
+        .and_then(|(key, _)| model.logs.source(key))
+        .and_then(|path| path.file_name())
+    {
+        let source = Line::from(format!(" {} ", source.to_string_lossy()).dim());
+        block = block.title(source.right_aligned());
+    }
+    block
+}
 
-    let package_info = model.alpm_service.package_info(package_name).ok();
-    let package_text = render_pkg_info(package_info);
-    Paragraph::new(package_text)
-        .wrap(Wrap { trim: false })
-        .block(block)
+pub fn package_text(model: &Model) -> Text<'static> {
+    let Some(package) = model.focused_package() else {
+        return render_summary(None);
+    };
+    match model.package_info.get(package) {
+        Some(Some(info)) => render_pkg_info(info),
+        Some(None) => render_summary(model.logs.package_summary(package)),
+        None => Text::from(Line::from("Looking up package…".dim())),
+    }
+}
+
+// In the order of pacman -Qi, split into sections
+fn render_pkg_info(info: &PackageInfo) -> Text<'static> {
+    let mut lines = vec![
+        i_section("General"),
+        i_line("Source", &info.source.to_string()),
+        i_line("Version", &info.version),
+        i_line("Description", &info.description),
+        i_option("Architecture", &info.arch),
+        i_option("URL", &info.url),
+    ];
+    lines.extend(i_items("Licenses", &info.licenses));
+    lines.extend(i_items("Groups", &info.groups));
+
+    lines.push(Line::default());
+    lines.push(i_section("Relations"));
+    lines.extend(i_items("Provides", &info.provides));
+    lines.extend(i_items("Conflicts with", &info.conflics_with));
+    lines.extend(i_items("Replaces", &info.replaces));
+
+    lines.push(Line::default());
+    lines.push(i_section("Build"));
+    lines.push(i_option("Packager", &info.packager));
+    lines.push(i_line("Build date", &render_timestamp(info.build_date)));
+
+    lines.push(Line::default());
+    lines.push(i_section("Install"));
+    lines.push(i_line("Installed size", &render_size(info.installed_size)));
+    let install_date = info.install_date.map(render_timestamp);
+    lines.push(i_line(
+        "Install date",
+        install_date.as_deref().unwrap_or("Not installed"),
+    ));
+    // Reason and validation are only recorded in the local database
+    if install_date.is_some() {
+        lines.push(i_line("Install reason", render_reason(info.install_reason)));
+    }
+    let install_script = if info.install_script { "Yes" } else { "No" };
+    lines.push(i_line("Install script", install_script));
+    if install_date.is_some() {
+        lines.push(i_line(
+            "Validated by",
+            &render_validation(info.validated_by),
+        ));
+    }
+    Text::from(lines)
+}
+
+fn render_reason(reason: PackageReason) -> &'static str {
+    match reason {
+        PackageReason::Explicit => "Explicitly installed",
+        PackageReason::Depend => "Installed as a dependency for another package",
+    }
+}
+
+fn render_validation(validation: PackageValidation) -> String {
+    if validation == PackageValidation::UNKNOWN {
+        return "Unknown".to_string();
+    }
+    let methods: Vec<&str> = [
+        (PackageValidation::NONE, "None"),
+        (PackageValidation::MD5SUM, "MD5 Sum"),
+        (PackageValidation::SHA256SUM, "SHA-256 Sum"),
+        (PackageValidation::SIGNATURE, "Signature"),
+    ]
+    .into_iter()
+    .filter(|(method, _)| validation.contains(*method))
+    .map(|(_, name)| name)
+    .collect();
+    methods.join("  ")
 }
 
-fn render_pkg_info(package_info: Option<PackageInfo>) -> Text<'static> {
-    if let Some(info) = package_info {
+// Fallback for packages that are no longer installed, in any repository or cached
+fn render_summary(summary: Option<PackageSummary>) -> Text<'static> {
+    if let Some(summary) = summary {
+        let first_installed = summary.first_installed.map(render_timestamp);
+        let removed = summary.removed.map(render_timestamp);
         Text::from(vec![
-            i_line("Version", &info.version),
-            i_line("Description", &info.description),
-            i_option("Architecture", &info.arch),
-            i_option("URL", &info.url),
-            i_list("Licenses", &info.licenses),
+            i_line("Source", "pacman.log"),
+            i_line("Last version", &summary.last_version),
+            i_option("First installed", &first_installed),
+            i_line("Last changed", &render_timestamp(summary.last_event)),
+            i_option("Removed", &removed),
         ])
     } else {
         Text::from(Line::from("No package selected".to_string()))
@@ -46,8 +151,21 @@ fn i_option(label: &str, value: &Option<String>) -> Line<'static> {
     i_line(label, value.as_ref().unwrap_or(&"None".to_string()))
 }
 
-fn i_list(label: &str, items: &[String]) -> Line<'static> {
-    i_line(label, &items.join(", "))
+// One item per line, so long lists stay readable when scrolling
+fn i_items(label: &str, items: &[String]) -> Vec<Line<'static>> {
+    let Some((first, rest)) = items.split_first() else {
+        return vec![i_line(label, "None")];
+    };
+    let mut lines = vec![i_line(label, first)];
+    lines.extend(
+        rest.iter()
+            .map(|item| Line::from(format!("{:<16}   {}", "", item))),
+    );
+    lines
+}
+
+fn i_section(title: &str) -> Line<'static> {
+    Line::from(title.to_string().bold().underlined())
 }
 
 fn i_line(label: &str, value: &str) -> Line<'static> {
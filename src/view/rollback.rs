@@ -0,0 +1,107 @@
+use ratatui::{
+    Frame,
+    prelude::*,
+    symbols::border,
+    widgets::{Block, List, ListItem, Paragraph},
+};
+
+use crate::{
+    format::render_timestamp,
+    model::{Model, Screen},
+    rollback::{RollbackPlan, RollbackStep},
+    view::SELECTED_STYLE,
+};
+
+pub fn render(model: &mut Model, index: usize, frame: &mut Frame) {
+    let Screen::Rollback { plan, state, .. } = &mut model.screens[index] else {
+        return;
+    };
+    let instructions = Line::from(vec![
+        " Down ".into(),
+        "<j>".blue().bold(),
+        " Up ".into(),
+        "<k>".blue().bold(),
+        " Write script ".into(),
+        "<w>".blue().bold(),
+        " Back ".into(),
+        "<Esc> ".blue().bold(),
+    ]);
+    let mut block = Block::bordered()
+        .title_bottom(instructions.centered())
+        .border_set(border::THICK);
+    if let Some(notice) = &model.notice {
+        block = block.title(Line::from(format!(" {} ", notice).bold()).left_aligned());
+    }
+
+    let Some(plan) = plan else {
+        let paragraph = Paragraph::new("Checking the package cache…".italic())
+            .centered()
+            .block(block.title(Line::from(" Rollback ".bold()).centered()));
+        frame.render_widget(paragraph, frame.area());
+        return;
+    };
+    let title = format!(
+        " Rollback of {} at {} ",
+        plan.command.as_deref().unwrap_or("transaction"),
+        render_timestamp(plan.transaction.timestamp)
+    );
+    block = block.title(Line::from(title.bold()).centered());
+    let missing = plan.missing().len();
+    if missing > 0 {
+        block = block.title(
+            Line::from(
+                format!(" {} not in the package cache ", missing)
+                    .yellow()
+                    .bold(),
+            )
+            .right_aligned(),
+        );
+    }
+
+    let list = List::new(render_steps(plan))
+        .block(block)
+        .highlight_style(SELECTED_STYLE)
+        .highlight_symbol(">")
+        .highlight_spacing(ratatui::widgets::HighlightSpacing::Always);
+    frame.render_stateful_widget(list, frame.area(), state);
+}
+
+fn render_steps(plan: &RollbackPlan) -> Vec<ListItem<'static>> {
+    if plan.steps.is_empty() {
+        return vec![ListItem::from(
+            "The transaction changed no packages".italic(),
+        )];
+    }
+    plan.steps
+        .iter()
+        .map(|step| match step {
+            RollbackStep::Install {
+                package,
+                version,
+                file: Some(file),
+            } => ListItem::from(vec![
+                Line::from(vec![
+                    "install ".green(),
+                    package.clone().bold(),
+                    format!(" {}", version).into(),
+                ]),
+                Line::from(format!("  {}", file.display()).dim()),
+            ]),
+            RollbackStep::Install {
+                package,
+                version,
+                file: None,
+            } => ListItem::from(vec![
+                Line::from(vec![
+                    "install ".red(),
+                    package.clone().bold(),
+                    format!(" {}", version).into(),
+                ]),
+                Line::from("  not in the package cache, left out".red()),
+            ]),
+            RollbackStep::Remove { package } => {
+                ListItem::from(Line::from(vec!["remove ".yellow(), package.clone().bold()]))
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,148 @@
+use ratatui::{
+    Frame,
+    prelude::*,
+    symbols::border,
+    widgets::{Bar, BarChart, BarGroup, Block, List, ListItem, Paragraph, Sparkline},
+};
+
+use crate::{
+    format::render_timestamp,
+    logparse::LogDB,
+    model::{Model, Screen},
+    stats::Stats,
+};
+
+const MONTH_BAR_WIDTH: u16 = 5;
+
+pub fn render(model: &mut Model, index: usize, frame: &mut Frame) {
+    let Screen::Stats(stats) = &model.screens[index] else {
+        return;
+    };
+    let instructions = Line::from(vec![" Back ".into(), "<Esc> ".blue().bold()]);
+    let block = Block::bordered()
+        .title(Line::from(" Statistics ".bold()).centered())
+        .title_bottom(instructions.centered())
+        .border_set(border::THICK);
+    let area = block.inner(frame.area());
+    frame.render_widget(block, frame.area());
+
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![
+            Constraint::Percentage(40),
+            Constraint::Length(6),
+            Constraint::Min(0),
+        ])
+        .split(area);
+    let lists = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![
+            Constraint::Percentage(30),
+            Constraint::Percentage(40),
+            Constraint::Percentage(30),
+        ])
+        .split(rows[2]);
+
+    frame.render_widget(render_months(stats, rows[0].width), rows[0]);
+    frame.render_widget(render_weeks(stats, rows[1].width), rows[1]);
+    frame.render_widget(render_most_upgraded(stats), lists[0]);
+    frame.render_widget(render_largest(&model.logs, stats), lists[1]);
+    frame.render_widget(render_summary(stats), lists[2]);
+}
+
+// As many of the most recent months as fit
+fn render_months(stats: &Stats, width: u16) -> BarChart<'static> {
+    let fit = (width.saturating_sub(2) / (MONTH_BAR_WIDTH + 1)) as usize;
+    let months = &stats.upgrades_per_month[stats.upgrades_per_month.len().saturating_sub(fit)..];
+    let bars: Vec<Bar> = months
+        .iter()
+        .map(|((year, month), count)| {
+            Bar::default()
+                .value(*count)
+                .label(format!("{:02}/{:02}", *month as u8, year % 100))
+        })
+        .collect();
+    BarChart::default()
+        .block(Block::bordered().title(" Upgrades per month "))
+        .bar_width(MONTH_BAR_WIDTH)
+        .bar_gap(1)
+        .bar_style(Style::new().cyan())
+        .value_style(Style::new().black().on_cyan())
+        .data(BarGroup::default().bars(&bars))
+}
+
+// One column per week, as many of the most recent weeks as fit
+fn render_weeks(stats: &Stats, width: u16) -> Sparkline<'static> {
+    let fit = width.saturating_sub(2) as usize;
+    let weeks = &stats.upgrades_per_week[stats.upgrades_per_week.len().saturating_sub(fit)..];
+    let title = match weeks.first() {
+        Some((start, _)) => format!(" Upgrades per week since {} ", render_date(*start)),
+        None => " Upgrades per week ".to_string(),
+    };
+    let counts: Vec<u64> = weeks.iter().map(|(_, count)| *count).collect();
+    Sparkline::default()
+        .block(Block::bordered().title(title))
+        .style(Style::new().cyan())
+        .data(&counts)
+}
+
+fn render_most_upgraded(stats: &Stats) -> List<'static> {
+    let items: Vec<ListItem> = stats
+        .most_upgraded
+        .iter()
+        .map(|(package, count)| {
+            ListItem::from(Line::from(vec![
+                format!("{:>5} ", count).cyan(),
+                package.clone().into(),
+            ]))
+        })
+        .collect();
+    List::new(items).block(Block::bordered().title(" Most upgraded "))
+}
+
+fn render_largest(db: &LogDB, stats: &Stats) -> List<'static> {
+    let items: Vec<ListItem> = stats
+        .largest_transactions
+        .iter()
+        .filter_map(|(key, count)| {
+            let tx = db.transactions.get(key)?;
+            Some(ListItem::from(Line::from(vec![
+                format!("{:>5} ", count).cyan(),
+                render_date(tx.start).into(),
+                format!(" {}", tx.command.as_deref().unwrap_or("")).dim(),
+            ])))
+        })
+        .collect();
+    List::new(items).block(Block::bordered().title(" Largest transactions "))
+}
+
+fn render_summary(stats: &Stats) -> Paragraph<'static> {
+    let mut lines = vec![
+        Line::from(vec![
+            "Transactions: ".bold(),
+            stats.transactions.to_string().into(),
+        ]),
+        Line::from(vec![
+            "Average size: ".bold(),
+            format!("{:.1} packages", stats.average_transaction_size).into(),
+        ]),
+        Line::from(vec![
+            "Downgrades: ".bold(),
+            stats.downgrades.to_string().into(),
+        ]),
+    ];
+    if let Some((start, end)) = stats.longest_sysupgrade_gap {
+        lines.push(Line::from(vec![
+            "Longest without -Syu: ".bold(),
+            format!("{} days", (end - start) / (24 * 60 * 60)).into(),
+        ]));
+        lines.push(Line::from(
+            format!("  {} to {}", render_date(start), render_date(end)).dim(),
+        ));
+    }
+    Paragraph::new(lines).block(Block::bordered().title(" Summary "))
+}
+
+fn render_date(timestamp: i64) -> String {
+    render_timestamp(timestamp)[..10].to_string()
+}
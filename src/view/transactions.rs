@@ -1,41 +1,82 @@
 use ratatui::{
     prelude::*,
-    style::palette::tailwind::SLATE,
     symbols::border,
-    widgets::{Block, List, ListItem},
+    widgets::{Block, List, ListItem, Paragraph},
 };
-use time::macros::format_description;
 
-use crate::logparse::{LogDB, LogEvent};
-
-const SELECTED_STYLE: Style = Style::new().bg(SLATE.c600).add_modifier(Modifier::BOLD);
+use crate::{
+    filter::Filter,
+    format::render_timestamp,
+    logparse::{LogDB, LogEvent, LogKey, Transaction, TransactionStatus},
+    model::Model,
+    view::SELECTED_STYLE,
+};
 
-pub fn render_transactions(db: &LogDB) -> List<'_> {
+pub fn render_transactions<'a>(
+    db: &'a LogDB,
+    visible: &[LogKey],
+    hits: &[usize],
+    filter: &Filter,
+    status: Option<Line<'a>>,
+) -> List<'a> {
     let title = Line::from(" Transactions ".bold());
     let instructions = Line::from(vec![
         " Down ".into(),
         "<j>".blue().bold(),
         " Up ".into(),
         "<k>".blue().bold(),
+        " History ".into(),
+        "<h>".blue().bold(),
+        " Filter ".into(),
+        "</>".blue().bold(),
+        " Export ".into(),
+        "<e>".blue().bold(),
+        " Rollback ".into(),
+        "<r>".blue().bold(),
+        " Compare ".into(),
+        "<m>".blue().bold(),
+        " Stats ".into(),
+        "<s>".blue().bold(),
+        " Packages ".into(),
+        "<p>".blue().bold(),
     ]);
-    let block = Block::bordered()
+    let mut block = Block::bordered()
         .title(title.centered())
         .title_bottom(instructions.centered())
         .border_set(border::THICK);
-    let items: Vec<ListItem> = db
-        .events
+    if !db.diagnostics.is_empty() {
+        let diagnostics = Line::from(vec![
+            format!(" {} lines could not be parsed ", db.diagnostics.len())
+                .yellow()
+                .bold(),
+            "<d> ".blue().bold(),
+        ]);
+        block = block.title(diagnostics.right_aligned());
+    }
+    if let Some(status) = status {
+        block = block.title(status.left_aligned());
+    }
+    let mut previous_tx = None;
+    let items: Vec<ListItem> = visible
         .iter()
-        .map(|(logkey, event)| {
-            let event_summary = Line::from(render_event_summary(event));
-            // First item in transaction has a date above it
-            let items = if logkey.offset == 0 {
-                vec![
-                    Line::from(render_timestamp(logkey.timestamp)).bold(),
-                    event_summary,
-                ]
+        .enumerate()
+        .filter_map(|(index, logkey)| Some((index, db.events.get_key_value(logkey)?)))
+        .map(|(index, (logkey, event))| {
+            // Events shown only for the transaction they are in are dimmed
+            let event_summary = if hits.is_empty() || hits.binary_search(&index).is_ok() {
+                render_highlighted_summary(event, filter)
             } else {
-                vec![event_summary]
+                Line::from(render_event_summary(event)).dim()
             };
+            // First visible item in transaction has a date above it
+            let tx = db.transaction_for(logkey);
+            let items = match tx {
+                Some(tx) if previous_tx != Some(&tx.key) => {
+                    vec![render_transaction_header(tx), event_summary]
+                }
+                _ => vec![event_summary],
+            };
+            previous_tx = tx.map(|tx| &tx.key);
             ListItem::from(items)
         })
         .collect();
@@ -47,7 +88,91 @@ pub fn render_transactions(db: &LogDB) -> List<'_> {
         .highlight_spacing(ratatui::widgets::HighlightSpacing::Always)
 }
 
-fn render_event_summary(event: &LogEvent) -> String {
+pub fn render_export_prompt() -> Paragraph<'static> {
+    let choices = Line::from(vec![
+        "Export visible events as ".into(),
+        "<j>".blue().bold(),
+        "son ".into(),
+        "<n>".blue().bold(),
+        "djson ".into(),
+        "<c>".blue().bold(),
+        "sv".into(),
+    ]);
+    let block = Block::bordered().border_set(border::THICK);
+    Paragraph::new(choices).block(block)
+}
+
+pub fn render_filter_bar(model: &Model) -> Paragraph<'_> {
+    let mut spans = vec!["/".blue().bold(), Span::raw(model.filter.query.as_str())];
+    if model.filter_editing {
+        spans.push("█".into());
+    }
+    let status = match &model.filter.error {
+        Some(error) => Line::from(format!(" {} ", error).red()),
+        None => Line::from(format!(" {} matches ", model.hits.len()).dim()),
+    };
+    let block = Block::bordered()
+        .title(status.right_aligned())
+        .border_set(border::THICK);
+    Paragraph::new(Line::from(spans)).block(block)
+}
+
+pub fn render_date_prompt(date: &str) -> Paragraph<'_> {
+    let spans = vec!["Compare at ".blue().bold(), Span::raw(date), "█".into()];
+    let block = Block::bordered()
+        .title(Line::from(" YYYY-MM-DD [HH:MM] ".dim()).right_aligned())
+        .border_set(border::THICK);
+    Paragraph::new(Line::from(spans)).block(block)
+}
+
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Loading progress, the outcome of the last action or whether the log is being followed
+pub fn render_status(model: &Model) -> Option<Line<'static>> {
+    if model.loading {
+        let frame = SPINNER[model.ticks % SPINNER.len()];
+        return Some(Line::from(
+            format!(" {} reading logs ", frame).cyan().bold(),
+        ));
+    }
+    if let Some(error) = model.load_error.as_ref().or(model.follow_error.as_ref()) {
+        return Some(Line::from(format!(" ✗ {} ", error).red().bold()));
+    }
+    if let Some(notice) = &model.notice {
+        return Some(Line::from(format!(" {} ", notice).bold()));
+    }
+    model
+        .following
+        .then(|| Line::from(" ● following ".green().bold()))
+}
+
+// Highlights the parts of the package name matched by the filter
+fn render_highlighted_summary(event: &LogEvent, filter: &Filter) -> Line<'static> {
+    let summary = render_event_summary(event);
+    let Some(package) = event.package() else {
+        return Line::from(summary);
+    };
+    let highlights = filter.highlights(package);
+    let Some(start) = summary.find(package).filter(|_| !highlights.is_empty()) else {
+        return Line::from(summary);
+    };
+
+    let mut spans = Vec::new();
+    let mut position = 0;
+    for range in highlights {
+        let (from, to) = (start + range.start, start + range.end);
+        if from < position {
+            continue;
+        }
+        spans.push(Span::raw(summary[position..from].to_string()));
+        spans.push(summary[from..to].to_string().black().on_yellow());
+        position = to;
+    }
+    spans.push(Span::raw(summary[position..].to_string()));
+    Line::from(spans)
+}
+
+pub fn render_event_summary(event: &LogEvent) -> String {
     match event {
         LogEvent::Downgraded {
             package,
@@ -62,14 +187,33 @@ fn render_event_summary(event: &LogEvent) -> String {
         LogEvent::Installed { package, version } => {
             format!("+ {} ({})", package, version)
         }
+        LogEvent::Reinstalled { package, version } => {
+            format!("⟳ {} ({})", package, version)
+        }
         LogEvent::Removed { package, version } => {
             format!("- {} ({})", package, version)
         }
+        LogEvent::Command { command } => format!("$ {}", command),
+        LogEvent::SyncDatabases => "synchronizing package lists".to_string(),
+        LogEvent::FullSystemUpgrade => "starting full system upgrade".to_string(),
+        LogEvent::Hook { name } => format!("⚙ {}", name),
+        LogEvent::Scriptlet { output } => format!("  {}", output),
+        LogEvent::Warning { message } => format!("! {}", message),
+        LogEvent::Error { message } => format!("✗ {}", message),
     }
 }
 
-fn render_timestamp(timestamp: i64) -> String {
-    let format = format_description!("[year]-[month]-[day] [hour]:[minute]:[second]");
-    let date = time::OffsetDateTime::from_unix_timestamp(timestamp).unwrap();
-    date.format(&format).unwrap()
+fn render_transaction_header(tx: &Transaction) -> Line<'static> {
+    let timestamp = Span::from(render_timestamp(tx.start)).bold();
+    if tx.implicit {
+        return Line::from(vec![timestamp, " implicit".dim().italic()]);
+    }
+    match tx.status {
+        TransactionStatus::Completed | TransactionStatus::NotStarted => Line::from(timestamp),
+        TransactionStatus::InProgress => Line::from(vec![timestamp, " in progress".cyan().bold()]),
+        TransactionStatus::Interrupted => {
+            Line::from(vec![timestamp, " interrupted".yellow().bold()])
+        }
+        TransactionStatus::Failed => Line::from(vec![timestamp, " failed".red().bold()]),
+    }
 }
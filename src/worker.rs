@@ -0,0 +1,288 @@
+//! Slow work done off the UI thread. Results are posted back as messages.
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
+use std::time::Duration;
+
+use log::{debug, warn};
+
+use crate::alpmutil::AlpmService;
+use crate::compare::Comparison;
+use crate::export::{self, ExportError, ExportFormat};
+use crate::follow::LogFollower;
+use crate::index;
+use crate::logparse::{LogDB, LogKey, ParseMode};
+use crate::message::Message;
+use crate::rollback::RollbackPlan;
+
+/// Reads and parses the log and its rotated siblings, which can take a while for years
+/// of history, then follows the live log if asked to
+pub fn spawn_log_loader(
+    sender: Sender<Message>,
+    path: PathBuf,
+    index: Option<PathBuf>,
+    mode: ParseMode,
+    follow: bool,
+) {
+    thread::spawn(move || {
+        let (logs, offset) = match index::load(&path, index.as_deref(), mode) {
+            Ok(loaded) => loaded,
+            Err(error) => {
+                let _ = sender.send(Message::LoadFailed(error.to_string()));
+                return;
+            }
+        };
+        let tail = logs.continuation();
+        if sender
+            .send(Message::LogsLoaded(Box::new(logs), follow))
+            .is_err()
+            || !follow
+        {
+            return;
+        }
+        // Pick up where reading the live log left off
+        follow_log(&sender, LogFollower::new(path, offset, mode), tail);
+    });
+}
+
+/// How often the live log is checked for new lines
+const FOLLOW_POLL_RATE: Duration = Duration::from_millis(250);
+
+// Parses appended lines into a continuation of the history, which is posted for the
+// model to merge whenever it has new events
+fn follow_log(sender: &Sender<Message>, mut follower: LogFollower, mut tail: LogDB) {
+    loop {
+        thread::sleep(FOLLOW_POLL_RATE);
+        let message = match follower.poll(&mut tail) {
+            Ok(false) => continue,
+            Ok(true) => {
+                let next = tail.continuation();
+                Message::LogAppended(Box::new(std::mem::replace(&mut tail, next)))
+            }
+            Err(error) => {
+                let _ = sender.send(Message::FollowFailed(error.to_string()));
+                return;
+            }
+        };
+        if sender.send(message).is_err() {
+            return;
+        }
+    }
+}
+
+/// Writes the events in `logs` to a new file at `path`, which takes a moment for years of
+/// history, and reports how that went
+pub fn spawn_export(sender: Sender<Message>, logs: LogDB, format: ExportFormat, path: PathBuf) {
+    thread::spawn(move || {
+        let message = match write_export(&logs, format, &path) {
+            Ok(()) => Message::Exported(path, logs.events.len()),
+            Err(error) => Message::ExportFailed(error.to_string()),
+        };
+        let _ = sender.send(message);
+    });
+}
+
+fn write_export(logs: &LogDB, format: ExportFormat, path: &Path) -> Result<(), ExportError> {
+    let file = File::create_new(path)?;
+    let keys: Vec<LogKey> = logs.events.keys().cloned().collect();
+    export::export(logs, &keys, format, BufWriter::new(file))
+}
+
+#[derive(Debug)]
+enum AlpmRequest {
+    PackageInfo(String),
+    Dependencies(String),
+    /// Find the package files a rollback needs in the cache
+    LocateRollback(Box<RollbackPlan>),
+    /// Look up the installed sizes of the packages in a comparison
+    SizeComparison(Box<Comparison>),
+    LocalPackages,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WorkerError {
+    #[error("Failed to open the package databases: {0}")]
+    Alpm(#[from] alpm::Error),
+
+    #[error("The alpm worker exited before opening the databases")]
+    Exited,
+}
+
+/// How often the worker checks whether pacman has written the databases
+const DB_POLL_RATE: Duration = Duration::from_secs(2);
+
+/// Handle to a thread that owns the alpm handle, which cannot be shared with the UI
+/// thread. Lookups are answered in the order they were requested. The handle is opened
+/// again whenever pacman writes the databases, which is announced before any answers
+/// from the new handle.
+#[derive(Debug)]
+pub struct AlpmWorker {
+    requests: Sender<AlpmRequest>,
+}
+
+impl AlpmWorker {
+    /// Opens the alpm handle on the worker thread and waits for it to be ready
+    pub fn spawn<F>(sender: Sender<Message>, open: F) -> Result<Self, WorkerError>
+    where
+        F: Fn() -> Result<AlpmService, alpm::Error> + Send + 'static,
+    {
+        let (requests, receiver) = mpsc::channel();
+        let (ready_sender, ready) = mpsc::channel();
+        thread::spawn(move || {
+            let mut service = match open() {
+                Ok(service) => {
+                    let _ = ready_sender.send(Ok(()));
+                    service
+                }
+                Err(error) => {
+                    let _ = ready_sender.send(Err(error));
+                    return;
+                }
+            };
+            let mut stamps = service.db_stamps();
+            loop {
+                let request = match receiver.recv_timeout(DB_POLL_RATE) {
+                    Ok(request) => Some(request),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                };
+                let current = service.db_stamps();
+                if let Some(change) = stamps.change(&current) {
+                    debug!("alpm worker: databases changed ({:?})", change);
+                    match open() {
+                        Ok(reopened) => service = reopened,
+                        Err(error) => warn!("could not reopen the alpm handle: {}", error),
+                    }
+                    stamps = current;
+                    if sender.send(Message::DatabasesChanged(change)).is_err() {
+                        break;
+                    }
+                }
+                let Some(request) = request else {
+                    continue;
+                };
+                debug!("alpm worker: {:?}", request);
+                let message = match request {
+                    AlpmRequest::PackageInfo(name) => {
+                        let info = service.package_info(&name).ok().map(Box::new);
+                        Message::PackageInfoLoaded(name, info)
+                    }
+                    AlpmRequest::Dependencies(name) => {
+                        let dependencies = service.dependencies(&name).ok().map(Box::new);
+                        Message::DependenciesLoaded(name, dependencies)
+                    }
+                    AlpmRequest::LocateRollback(mut plan) => {
+                        plan.locate(&service.scan_cache());
+                        Message::RollbackPlanned(plan)
+                    }
+                    AlpmRequest::SizeComparison(mut comparison) => {
+                        comparison.size_deltas = Some(service.size_deltas(&comparison.changes));
+                        Message::ComparisonSized(comparison)
+                    }
+                    AlpmRequest::LocalPackages => {
+                        Message::LocalPackagesLoaded(service.local_packages())
+                    }
+                };
+                if sender.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        ready.recv().map_err(|_| WorkerError::Exited)??;
+        Ok(AlpmWorker { requests })
+    }
+
+    pub fn package_info(&self, package: &str) {
+        let _ = self
+            .requests
+            .send(AlpmRequest::PackageInfo(package.to_string()));
+    }
+
+    pub fn dependencies(&self, package: &str) {
+        let _ = self
+            .requests
+            .send(AlpmRequest::Dependencies(package.to_string()));
+    }
+
+    pub fn locate_rollback(&self, plan: RollbackPlan) {
+        let _ = self
+            .requests
+            .send(AlpmRequest::LocateRollback(Box::new(plan)));
+    }
+
+    pub fn size_comparison(&self, comparison: Comparison) {
+        let _ = self
+            .requests
+            .send(AlpmRequest::SizeComparison(Box::new(comparison)));
+    }
+
+    pub fn local_packages(&self) {
+        let _ = self.requests.send(AlpmRequest::LocalPackages);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logparse::parse_fixture;
+    use crate::testutil::temp_dir;
+
+    #[test]
+    fn test_spawn_log_loader() {
+        let dir = temp_dir("worker");
+        let path = dir.join("pacman.log");
+        let log = "[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)\n";
+        std::fs::write(&path, log).unwrap();
+
+        let (sender, receiver) = mpsc::channel();
+        spawn_log_loader(sender, path.clone(), None, ParseMode::Strict, true);
+        let Message::LogsLoaded(mut logs, true) = receiver.recv().unwrap() else {
+            panic!("expected the logs to load");
+        };
+        assert_eq!(logs.events.len(), 1);
+
+        // Lines appended later arrive as a continuation of the history
+        let appended = "[2026-01-28T19:36:51-0800] [ALPM] installed puckrat (0.1.0-1)\n";
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        std::io::Write::write_all(&mut file, appended.as_bytes()).unwrap();
+        let message = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let Message::LogAppended(tail) = message else {
+            panic!("expected the appended lines");
+        };
+        assert_eq!(tail.events.len(), 1);
+        logs.merge(*tail);
+        assert_eq!(logs.events.len(), 2);
+    }
+
+    #[test]
+    fn test_spawn_export() {
+        let dir = temp_dir("export");
+        let path = dir.join("export.ndjson");
+        let logs = parse_fixture("[2026-01-28T19:36:50-0800] [ALPM] installed gelly (4.1.0-1)\n");
+
+        let keys: Vec<LogKey> = logs.events.keys().cloned().collect();
+
+        let (sender, receiver) = mpsc::channel();
+        spawn_export(
+            sender.clone(),
+            logs.excerpt(&keys),
+            ExportFormat::Ndjson,
+            path.clone(),
+        );
+        let Message::Exported(_, 1) = receiver.recv().unwrap() else {
+            panic!("expected the export to be written");
+        };
+        // Exports never overwrite a file
+        spawn_export(sender, logs, ExportFormat::Ndjson, path.clone());
+        let message = receiver.recv().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(matches!(message, Message::ExportFailed(_)));
+    }
+}